@@ -77,7 +77,17 @@ router.get(
 router.put(
   "/summoners",
   asyncHandler(async (req, res) => {
-    const { puuid, gameName, tagLine, region, tier, rank, lp } = req.body;
+    const {
+      puuid,
+      gameName,
+      tagLine,
+      region,
+      matchRegionPrefix,
+      regionGroup,
+      tier,
+      rank,
+      lp,
+    } = req.body;
 
     if (!puuid || !gameName || !tagLine || !region) {
       return res.status(400).json({
@@ -89,24 +99,87 @@ router.put(
     await queryOne(
       `
     INSERT INTO public.summoners
-    (puuid, "gameName", "tagLine", region, tier, rank, lp, "lastUpdated")
-    VALUES ($1, $2, $3, $4, COALESCE($5, 'UNRANKED'), $6, COALESCE($7, 0), now())
+    (puuid, "gameName", "tagLine", region, "matchRegionPrefix", "regionGroup",
+     tier, rank, lp, "lastUpdated")
+    VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 'UNRANKED'), $8, COALESCE($9, 0), now())
     ON CONFLICT (puuid) DO UPDATE
     SET "gameName" = EXCLUDED."gameName",
         "tagLine" = EXCLUDED."tagLine",
         region = EXCLUDED.region,
+        "matchRegionPrefix" = COALESCE(EXCLUDED."matchRegionPrefix", summoners."matchRegionPrefix"),
+        "regionGroup" = COALESCE(EXCLUDED."regionGroup", summoners."regionGroup"),
         tier = EXCLUDED.tier,
         rank = EXCLUDED.rank,
         lp = EXCLUDED.lp,
         "lastUpdated" = now()
   `,
-      [puuid, gameName, tagLine, region, tier, rank, lp],
+      [
+        puuid,
+        gameName,
+        tagLine,
+        region,
+        matchRegionPrefix,
+        regionGroup,
+        tier,
+        rank,
+        lp,
+      ],
     );
 
     res.json({ success: true });
   }),
 );
 
+// POST /db/matches/exists - matches_exist
+router.post(
+  "/matches/exists",
+  asyncHandler(async (req, res) => {
+    const { matchIds } = req.body;
+
+    if (!Array.isArray(matchIds)) {
+      return res.status(400).json({
+        error: "Missing required field: matchIds",
+        statusCode: 400,
+      });
+    }
+
+    const rows = await queryMany(
+      `
+    SELECT "matchId" FROM public.match_details WHERE "matchId" = ANY($1)
+  `,
+      [matchIds],
+    );
+
+    res.json({ existing: rows.map((row) => row.matchId) });
+  }),
+);
+
+// POST /db/matches/oldest-creation - oldest_game_creation
+router.post(
+  "/matches/oldest-creation",
+  asyncHandler(async (req, res) => {
+    const { matchIds } = req.body;
+
+    if (!Array.isArray(matchIds)) {
+      return res.status(400).json({
+        error: "Missing required field: matchIds",
+        statusCode: 400,
+      });
+    }
+
+    const row = await queryOne(
+      `
+    SELECT MIN("gameCreation") AS oldest
+    FROM public.match_details
+    WHERE "matchId" = ANY($1)
+  `,
+      [matchIds],
+    );
+
+    res.json({ oldest: row.oldest === null ? null : parseInt(row.oldest, 10) });
+  }),
+);
+
 // GET /db/matches/:matchId/exists - match_exists
 router.get(
   "/matches/:matchId/exists",
@@ -212,11 +285,110 @@ router.get(
   }),
 );
 
-// POST /db/matches - insert_match_details_tx (with transaction support)
-router.post(
-  "/matches",
+// GET /db/matches/:puuid/missing-timelines - get_matches_missing_timeline
+router.get(
+  "/matches/:puuid/missing-timelines",
   asyncHandler(async (req, res) => {
-    const {
+    const { puuid } = req.params;
+    const { limit = 20 } = req.query;
+
+    const matches = await queryMany(
+      `
+    SELECT md.mid, md."matchId", md."entryPlayerPuuid", md."gameCreation"
+    FROM public.match_details md
+    WHERE md."entryPlayerPuuid" = $1
+      AND NOT EXISTS (
+        SELECT 1 FROM public.match_timeline mt WHERE mt.mid = md.mid
+      )
+      AND NOT EXISTS (
+        SELECT 1 FROM public.timeline_unavailable tu WHERE tu.mid = md.mid
+      )
+    ORDER BY md."gameCreation" DESC
+    LIMIT $2
+  `,
+      [puuid, parseInt(limit, 10)],
+    );
+
+    // Convert numeric fields to proper types
+    matches.forEach((match) => {
+      match.mid =
+        typeof match.mid === "string" ? parseInt(match.mid, 10) : match.mid;
+      match.gameCreation =
+        typeof match.gameCreation === "string"
+          ? parseInt(match.gameCreation, 10)
+          : match.gameCreation;
+    });
+
+    res.json(matches);
+  }),
+);
+
+// PUT /db/timeline-unavailable/:mid - mark_timeline_unavailable
+router.put(
+  "/timeline-unavailable/:mid",
+  asyncHandler(async (req, res) => {
+    const mid = parseInt(req.params.mid, 10);
+    const { reason } = req.body;
+
+    if (Number.isNaN(mid) || !reason) {
+      return res.status(400).json({
+        error: "Missing required fields: mid, reason",
+        statusCode: 400,
+      });
+    }
+
+    await queryOne(
+      `
+    INSERT INTO public.timeline_unavailable (mid, reason)
+    VALUES ($1, $2)
+    ON CONFLICT (mid) DO UPDATE SET reason = EXCLUDED.reason
+  `,
+      [mid, reason],
+    );
+
+    res.json({ success: true });
+  }),
+);
+
+/**
+ * Insert a match_details row inside a transaction and return its mid. A match
+ * that is already stored keeps its row, same as the app's own stores
+ */
+async function insertMatchDetails(client, match) {
+  const {
+    matchId,
+    entryPuuid,
+    gameVersion,
+    gameCreation,
+    gameStart,
+    gameEnd,
+    gameDuration,
+    gameMode,
+    gameType,
+    queueType,
+    mapName,
+    participants,
+    teams,
+  } = match;
+
+  // Ensure JSON data is properly serialized
+  const participantsJson =
+    typeof participants === "string"
+      ? participants
+      : JSON.stringify(participants);
+  const teamsJson = typeof teams === "string" ? teams : JSON.stringify(teams);
+
+  const matchResult = await client.query(
+    `
+      INSERT INTO public.match_details
+      ("matchId", "entryPlayerPuuid", "gameVersion", "gameCreation",
+       "gameStartTime", "gameEndTime", "gameDuration", "gameMode",
+       "gameType", "queueType", "mapName", participants, teams)
+      VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
+      ON CONFLICT ("matchId") DO UPDATE SET "matchId" = EXCLUDED."matchId"
+      RETURNING mid
+    `,
+    [
       matchId,
       entryPuuid,
       gameVersion,
@@ -228,6 +400,136 @@ router.post(
       gameType,
       queueType,
       mapName,
+      participantsJson,
+      teamsJson,
+    ],
+  );
+
+  const mid = matchResult.rows[0]?.mid;
+
+  if (!mid) {
+    throw new Error(
+      `Failed to get match ID for ${matchId}. Match insertion may have failed.`,
+    );
+  }
+
+  await insertMatchParticipants(client, mid);
+
+  console.log(`[Database] Match ${matchId} stored with mid: ${mid}`);
+  return mid;
+}
+
+/**
+ * Fill match_participants from the participants JSONB of a match_details row
+ */
+async function insertMatchParticipants(client, mid) {
+  await client.query(
+    `
+      INSERT INTO public.match_participants
+      (mid, puuid, team_id, champion_name, team_position, kills, deaths, assists, cs,
+       gold_earned, damage_to_champions, vision_score, win)
+      SELECT
+        md.mid,
+        p->>'puuid',
+        (p->>'teamId')::int,
+        p->>'championName',
+        NULLIF(p->>'teamPosition', ''),
+        COALESCE((p->>'kills')::int, 0),
+        COALESCE((p->>'deaths')::int, 0),
+        COALESCE((p->>'assists')::int, 0),
+        COALESCE((p->>'totalMinionsKilled')::int, 0)
+          + COALESCE((p->>'neutralMinionsKilled')::int, 0),
+        COALESCE((p->>'goldEarned')::int, 0),
+        COALESCE((p->>'totalDamageDealtToChampions')::int, 0),
+        COALESCE((p->>'visionScore')::int, 0),
+        COALESCE((p->>'win')::boolean, false)
+      FROM public.match_details md
+      JOIN LATERAL jsonb_array_elements(md.participants) p ON true
+      WHERE md.mid = $1 AND p->>'puuid' IS NOT NULL
+      ON CONFLICT (mid, puuid) DO NOTHING
+    `,
+    [mid],
+  );
+}
+
+/**
+ * Insert one match_timeline frame inside a transaction
+ */
+async function insertTimelineFrame(client, mid, frame) {
+  const {
+    entryParticipantId,
+    frameIndex,
+    timestamp,
+    participantFrames,
+    events,
+  } = frame;
+
+  // Ensure JSON data is properly serialized
+  const participantFramesJson =
+    typeof participantFrames === "string"
+      ? participantFrames
+      : JSON.stringify(participantFrames);
+  const eventsJson =
+    typeof events === "string" ? events : JSON.stringify(events);
+
+  await client.query(
+    `
+      INSERT INTO public.match_timeline
+      (mid, "entryParticipantId", "frameIndex", "timestamp",
+       "participantFrames", events)
+      VALUES ($1,$2,$3,$4,$5,$6)
+      ON CONFLICT DO NOTHING
+    `,
+    [
+      mid,
+      entryParticipantId,
+      frameIndex,
+      timestamp,
+      participantFramesJson,
+      eventsJson,
+    ],
+  );
+}
+
+/**
+ * Insert many match_timeline frames of one match with a single statement
+ */
+async function insertTimelineFrames(client, mid, entryParticipantId, frames) {
+  if (frames.length === 0) {
+    return;
+  }
+
+  const toJson = (value) =>
+    typeof value === "string" ? value : JSON.stringify(value);
+
+  await client.query(
+    `
+      INSERT INTO public.match_timeline
+      (mid, "entryParticipantId", "frameIndex", "timestamp",
+       "participantFrames", events)
+      SELECT $1, $2, f."frameIndex", f."timestamp", f."participantFrames", f.events
+      FROM unnest($3::int[], $4::bigint[], $5::jsonb[], $6::jsonb[])
+        AS f("frameIndex", "timestamp", "participantFrames", events)
+      ON CONFLICT DO NOTHING
+    `,
+    [
+      mid,
+      entryParticipantId,
+      frames.map((f) => f.frameIndex),
+      frames.map((f) => f.timestamp),
+      frames.map((f) => toJson(f.participantFrames)),
+      frames.map((f) => toJson(f.events)),
+    ],
+  );
+}
+
+// POST /db/matches - insert_match_details_tx (with transaction support)
+router.post(
+  "/matches",
+  asyncHandler(async (req, res) => {
+    const {
+      matchId,
+      entryPuuid,
       participants,
       teams,
       timelineFrames, // Optional array of timeline frames to insert in same transaction
@@ -242,90 +544,12 @@ router.post(
     }
 
     const result = await transaction(async (client) => {
-      // Insert match details - ensure JSON data is properly serialized
-      const participantsJson =
-        typeof participants === "string"
-          ? participants
-          : JSON.stringify(participants);
-      const teamsJson =
-        typeof teams === "string" ? teams : JSON.stringify(teams);
-
-      const matchResult = await client.query(
-        `
-      INSERT INTO public.match_details
-      ("matchId", "entryPlayerPuuid", "gameVersion", "gameCreation",
-       "gameStartTime", "gameEndTime", "gameDuration", "gameMode",
-       "gameType", "queueType", "mapName", participants, teams)
-      VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
-      ON CONFLICT ("matchId") DO UPDATE SET
-        "entryPlayerPuuid" = EXCLUDED."entryPlayerPuuid"
-      RETURNING mid
-    `,
-        [
-          matchId,
-          entryPuuid,
-          gameVersion,
-          gameCreation,
-          gameStart,
-          gameEnd,
-          gameDuration,
-          gameMode,
-          gameType,
-          queueType,
-          mapName,
-          participantsJson,
-          teamsJson,
-        ],
-      );
-
-      const mid = matchResult.rows[0]?.mid;
-
-      if (!mid) {
-        throw new Error(
-          `Failed to get match ID for ${matchId}. Match insertion may have failed.`,
-        );
-      }
-
-      console.log(
-        `[Database] Match ${matchId} inserted/updated with mid: ${mid}`,
-      );
+      const mid = await insertMatchDetails(client, req.body);
 
       // Insert timeline frames if provided
-      if (timelineFrames && Array.isArray(timelineFrames) && mid) {
+      if (timelineFrames && Array.isArray(timelineFrames)) {
         for (const frame of timelineFrames) {
-          const {
-            entryParticipantId,
-            frameIndex,
-            timestamp,
-            participantFrames,
-            events,
-          } = frame;
-
-          // Ensure JSON data is properly serialized
-          const participantFramesJson =
-            typeof participantFrames === "string"
-              ? participantFrames
-              : JSON.stringify(participantFrames);
-          const eventsJson =
-            typeof events === "string" ? events : JSON.stringify(events);
-
-          await client.query(
-            `
-          INSERT INTO public.match_timeline
-          (mid, "entryParticipantId", "frameIndex", "timestamp",
-           "participantFrames", events)
-          VALUES ($1,$2,$3,$4,$5,$6)
-          ON CONFLICT DO NOTHING
-        `,
-            [
-              mid,
-              entryParticipantId,
-              frameIndex,
-              timestamp,
-              participantFramesJson,
-              eventsJson,
-            ],
-          );
+          await insertTimelineFrame(client, mid, frame);
         }
       }
 
@@ -336,6 +560,83 @@ router.post(
   }),
 );
 
+// POST /db/ingest - ProxyTx::commit
+// Applies a batch of writes in one transaction: all of them or none.
+// Matches inserted in the batch get a `ref`; later operations point at them with
+// `matchRef` instead of `mid`, since their mid is only known here.
+router.post(
+  "/ingest",
+  asyncHandler(async (req, res) => {
+    const { operations } = req.body;
+
+    if (!Array.isArray(operations)) {
+      return res.status(400).json({
+        error: "Missing required field: operations",
+        statusCode: 400,
+      });
+    }
+
+    console.log(
+      `[Database] ➡️  POST /ingest with ${operations.length} operations`,
+    );
+
+    const mids = await transaction(async (client) => {
+      const refs = {};
+
+      for (const op of operations) {
+        switch (op.type) {
+          case "match": {
+            if (
+              !op.matchId ||
+              !op.entryPuuid ||
+              !op.participants ||
+              !op.teams
+            ) {
+              throw new Error(
+                "match operation is missing matchId, entryPuuid, participants or teams",
+              );
+            }
+            const mid = await insertMatchDetails(client, op);
+            refs[op.ref] = typeof mid === "string" ? parseInt(mid, 10) : mid;
+            break;
+          }
+          case "timelineFrame": {
+            const mid = op.matchRef !== undefined ? refs[op.matchRef] : op.mid;
+            if (!mid || !op.entryParticipantId) {
+              throw new Error(
+                "timelineFrame operation is missing mid or entryParticipantId",
+              );
+            }
+            await insertTimelineFrame(client, mid, op);
+            break;
+          }
+          case "timelineFrames": {
+            const mid = op.matchRef !== undefined ? refs[op.matchRef] : op.mid;
+            if (!mid || !op.entryParticipantId || !Array.isArray(op.frames)) {
+              throw new Error(
+                "timelineFrames operation is missing mid, entryParticipantId or frames",
+              );
+            }
+            await insertTimelineFrames(
+              client,
+              mid,
+              op.entryParticipantId,
+              op.frames,
+            );
+            break;
+          }
+          default:
+            throw new Error(`Unknown ingest operation type: ${op.type}`);
+        }
+      }
+
+      return refs;
+    });
+
+    res.json({ mids });
+  }),
+);
+
 // POST /db/timeline-frames - insert_timeline_frame_tx
 router.post(
   "/timeline-frames",
@@ -395,6 +696,27 @@ router.post(
   }),
 );
 
+// POST /db/timeline-frames/batch - insert_timeline_frames
+router.post(
+  "/timeline-frames/batch",
+  asyncHandler(async (req, res) => {
+    const { mid, entryParticipantId, frames } = req.body;
+
+    if (!mid || !entryParticipantId || !Array.isArray(frames)) {
+      return res.status(400).json({
+        error: "Missing required fields: mid, entryParticipantId, frames",
+        statusCode: 400,
+      });
+    }
+
+    await transaction((client) =>
+      insertTimelineFrames(client, mid, entryParticipantId, frames),
+    );
+
+    res.json({ inserted: frames.length });
+  }),
+);
+
 // GET /db/timeline-frames/:mid/:frameIndex/exists - timeline_frame_exists_tx
 router.get(
   "/timeline-frames/:mid/:frameIndex/exists",
@@ -416,32 +738,81 @@ router.get(
   }),
 );
 
-// GET /db/summaries/:puuid - compute_player_summary
+/**
+ * Queue IDs from a comma separated `queueIds` query parameter, null (every queue)
+ * when it is left out
+ */
+function parseQueueIds(param) {
+  if (!param) {
+    return null;
+  }
+  return param
+    .split(",")
+    .map((id) => parseInt(id, 10))
+    .filter((id) => !Number.isNaN(id));
+}
+
+// GET /db/summaries/:puuid/results?queue=&queueIds= - results compute_player_summary
+// takes its streaks from, oldest first
 router.get(
+  "/summaries/:puuid/results",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+    const queueIds = parseQueueIds(req.query.queueIds);
+
+    const rows = await queryMany(
+      `
+    SELECT mp.win,
+           md."gameCreation" AS game_creation,
+           COALESCE(md."gameDuration",0) AS game_duration
+    FROM public.match_participants mp
+    JOIN public.match_details md ON md.mid = mp.mid
+    WHERE mp.puuid = $1
+      AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+      AND md."gameCreation" IS NOT NULL
+    ORDER BY md."gameCreation"
+  `,
+      [puuid, queueIds],
+    );
+
+    res.json(
+      rows.map((row) => ({
+        win: row.win,
+        game_creation: parseInt(row.game_creation, 10),
+        game_duration: row.game_duration,
+      })),
+    );
+  }),
+);
+
+// POST /db/summaries/:puuid?queue=&queueIds= - compute_player_summary
+// The body holds the streaks the app computed from /summaries/:puuid/results
+router.post(
   "/summaries/:puuid",
   asyncHandler(async (req, res) => {
     const { puuid } = req.params;
+    const streaks = {
+      current: req.body.current || 0,
+      longestWin: req.body.longest_win || 0,
+      longestLoss: req.body.longest_loss || 0,
+    };
+    const queue = req.query.queue || "ranked_solo";
+    const queueIds = parseQueueIds(req.query.queueIds);
 
     const result = await queryOne(
       `
-    WITH me AS (
+    WITH ranked AS (
       SELECT
-        md."matchId" AS match_id,
-        md."gameCreation" AS game_creation,
-        (p->>'win')::boolean AS win,
-        (p->>'kills')::int  AS k,
-        (p->>'deaths')::int AS d,
-        (p->>'assists')::int AS a,
-        (p->>'championName')::text AS champion,
-        md."queueType" AS queue_id,
-        md."gameDuration" AS duration_s
-      FROM public.match_details md
-      JOIN LATERAL jsonb_array_elements(md.participants) p ON true
-      WHERE p->>'puuid' = $1
-    ),
-    ranked AS (
-      SELECT * FROM me
-      WHERE queue_id = 420 AND COALESCE(duration_s,0) >= 300
+        mp.win,
+        mp.kills AS k,
+        mp.deaths AS d,
+        mp.assists AS a,
+        mp.champion_name AS champion
+      FROM public.match_participants mp
+      JOIN public.match_details md ON md.mid = mp.mid
+      WHERE mp.puuid = $1
+        AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+        AND COALESCE(md."gameDuration",0) >= 300
     ),
     sums AS (
       SELECT
@@ -492,7 +863,7 @@ router.get(
         ) AS top_champs
         FROM sums;
   `,
-      [puuid],
+      [puuid, queueIds],
     );
 
     if (!result) {
@@ -503,6 +874,9 @@ router.get(
         tk: 0,
         td: 0,
         ta: 0,
+        streak: 0,
+        longest_win_streak: 0,
+        longest_loss_streak: 0,
         top_champs: [],
       });
     }
@@ -528,21 +902,25 @@ router.get(
     await queryOne(
       `
     INSERT INTO public.player_summary
-    (puuid, queue_id, games, wins, losses, avg_kda, winrate, streak, top_champs, computed_at)
-    VALUES ($1,$2,$3,$4,$5,$6::float8,$7::float8,$8,$9, now())
-    ON CONFLICT (puuid) DO UPDATE
-    SET queue_id=$2, games=$3, wins=$4, losses=$5,
-        avg_kda=$6::float8, winrate=$7::float8, streak=$8, top_champs=$9, computed_at=now()
+    (puuid, queue, games, wins, losses, avg_kda, winrate, streak,
+     longest_win_streak, longest_loss_streak, top_champs, computed_at)
+    VALUES ($1,$2,$3,$4,$5,$6::float8,$7::float8,$8,$9,$10,$11, now())
+    ON CONFLICT (puuid, queue) DO UPDATE
+    SET games=$3, wins=$4, losses=$5,
+        avg_kda=$6::float8, winrate=$7::float8, streak=$8, longest_win_streak=$9,
+        longest_loss_streak=$10, top_champs=$11, computed_at=now()
   `,
       [
         puuid,
-        420,
+        queue,
         games,
         wins,
         losses,
         parseFloat(kda.toFixed(2)),
         parseFloat(winrate.toFixed(2)),
-        0,
+        streaks.current,
+        streaks.longestWin,
+        streaks.longestLoss,
         JSON.stringify(champsWithIcons),
       ],
     );
@@ -553,19 +931,21 @@ router.get(
       losses,
       kda: parseFloat((kda * 100).toFixed(0)) / 100,
       winrate: parseFloat((winrate * 100).toFixed(0)) / 100,
-      streak: 0,
+      streak: streaks.current,
+      longest_win_streak: streaks.longestWin,
+      longest_loss_streak: streaks.longestLoss,
       top_champs: champsWithIcons,
     };
 
     console.log(
-      `[Database] GET /summaries/${puuid} response:`,
+      `[Database] POST /summaries/${puuid} response:`,
       JSON.stringify(summaryResponse),
     );
     res.json(summaryResponse);
   }),
 );
 
-// GET /db/activity/:puuid - get_daily_activity
+// GET /db/activity/:puuid?queueIds= - get_daily_activity
 router.get(
   "/activity/:puuid",
   asyncHandler(async (req, res) => {
@@ -575,13 +955,14 @@ router.get(
       `
     WITH daily_counts AS (
         SELECT 
-            DATE(to_timestamp("gameCreation" / 1000)) as game_date,
+            DATE(to_timestamp(md."gameCreation" / 1000)) as game_date,
             COUNT(*) as games
-        FROM public.match_details
-        WHERE "entryPlayerPuuid" = $1
-            AND "gameCreation" >= extract(epoch from (now() - interval '30 days')) * 1000
-            AND "queueType" = 420
-        GROUP BY DATE(to_timestamp("gameCreation" / 1000))
+        FROM public.match_participants mp
+        JOIN public.match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+            AND md."gameCreation" >= extract(epoch from (now() - interval '30 days')) * 1000
+            AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+        GROUP BY DATE(to_timestamp(md."gameCreation" / 1000))
     ),
     date_series AS (
         SELECT generate_series(
@@ -597,209 +978,406 @@ router.get(
     LEFT JOIN daily_counts dc ON ds.date = dc.game_date
     ORDER BY ds.date
   `,
-      [puuid],
+      [puuid, parseQueueIds(req.query.queueIds)],
     );
 
     res.json(activities);
   }),
 );
 
-// GET /db/rank-progress/:puuid - compute_rank_progress_and_cache
-router.get(
-  "/rank-progress/:puuid",
+// POST /db/rank-snapshots/:puuid - save_rank_snapshot
+router.post(
+  "/rank-snapshots/:puuid",
   asyncHandler(async (req, res) => {
     const { puuid } = req.params;
-    const { currentTier, currentDivision, currentLp } = req.query;
+    const { tier, division, lp, taken_at } = req.body;
 
-    if (!currentTier || !currentDivision || currentLp === undefined) {
+    if (!tier || !division || lp === undefined || taken_at === undefined) {
       return res.status(400).json({
-        error:
-          "Missing required query parameters: currentTier, currentDivision, currentLp",
+        error: "Missing required fields: tier, division, lp, taken_at",
         statusCode: 400,
       });
     }
 
-    const matches = await queryMany(
+    await queryOne(
       `
-    SELECT m."matchId", m."gameCreation", (p->>'win')::bool as win
-    FROM public.match_details m,
-    LATERAL jsonb_array_elements(m.participants) as p
-    WHERE p->>'puuid' = $1
+    INSERT INTO public.rank_snapshots (puuid, tier, division, lp, taken_at)
+    VALUES ($1,$2,$3,$4,$5)
+  `,
+      [puuid, tier, division, lp, taken_at],
+    );
+
+    res.json({ success: true });
+  }),
+);
+
+// GET /db/rank-history/:puuid?limit= - ranked games and rank snapshots for
+// compute_rank_progress_and_cache, which works out the steps in the app
+router.get(
+  "/rank-history/:puuid",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+    const limit = parseInt(req.query.limit, 10) || 11;
+
+    const games = await queryMany(
+      `
+    SELECT m."matchId" AS match_id,
+           m."gameCreation" AS game_creation,
+           m."gameCreation" + COALESCE(m."gameDuration", 0)::bigint * 1000 AS game_end,
+           mp.win
+    FROM public.match_participants mp
+    JOIN public.match_details m ON m.mid = mp.mid
+    WHERE mp.puuid = $1
       AND m."queueType" = 420
     ORDER BY m."gameCreation" DESC
-    LIMIT 10
+    LIMIT $2
   `,
-      [puuid],
+      [puuid, limit],
     );
 
-    const steps = [];
-    let lp = parseInt(currentLp, 10);
-
-    // Process matches in reverse order (oldest to newest)
-    for (let i = matches.length - 1; i >= 0; i--) {
-      const match = matches[i];
-      const labelIndex = matches.length - i;
-      const delta = match.win ? 15 : -15;
-      const before = lp - delta;
-
-      const step = {
-        label_index: labelIndex,
-        lp_before: before,
-        lp_after: lp,
-        lp_delta: delta,
-        result: match.win ? "Win" : "Loss",
-        tier_before: currentTier,
-        division_before: currentDivision,
-        tier_after: currentTier,
-        division_after: currentDivision,
-        exact: false,
-        match_id: match.matchId,
-      };
+    // From the last snapshot before the oldest game on
+    const oldest = games.length ? games[games.length - 1].game_creation : 0;
+    const snapshots = await queryMany(
+      `
+    SELECT tier, division, lp, taken_at
+    FROM public.rank_snapshots
+    WHERE puuid = $1
+      AND taken_at >= COALESCE(
+        (SELECT MAX(taken_at) FROM public.rank_snapshots WHERE puuid = $1 AND taken_at < $2),
+        $2)
+    ORDER BY taken_at
+  `,
+      [puuid, oldest ?? 0],
+    );
 
-      // Cache the rank progress
-      await queryOne(
-        `
-      INSERT INTO public.match_rank_progress
-      (puuid, match_id, game_creation, lp_before, lp_after, lp_delta,
-       tier_before, division_before, tier_after, division_after, exact)
-      VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,false)
-      ON CONFLICT (puuid, match_id) DO NOTHING
-    `,
-        [
-          puuid,
-          step.match_id,
-          match.gameCreation,
-          step.lp_before,
-          step.lp_after,
-          step.lp_delta,
-          step.tier_before,
-          step.division_before,
-          step.tier_after,
-          step.division_after,
-        ],
-      );
+    // BIGINT columns come back as strings
+    const toInt = (v) => (v === null ? null : parseInt(v, 10));
+    res.json({
+      games: games.map((g) => ({
+        ...g,
+        game_creation: toInt(g.game_creation),
+        game_end: toInt(g.game_end),
+      })),
+      snapshots: snapshots.map((s) => ({ ...s, taken_at: toInt(s.taken_at) })),
+    });
+  }),
+);
 
-      steps.push(step);
-      lp = before;
+// PUT /db/rank-progress/:puuid - cache the steps of compute_rank_progress_and_cache.
+// Exact steps are kept, estimated ones are replaced.
+router.put(
+  "/rank-progress/:puuid",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+    const { steps } = req.body;
+
+    if (!Array.isArray(steps)) {
+      return res.status(400).json({
+        error: "Missing required field: steps",
+        statusCode: 400,
+      });
     }
 
-    res.json(steps);
+    await transaction(async (client) => {
+      for (const step of steps) {
+        await client.query(
+          `
+        INSERT INTO public.match_rank_progress
+        (puuid, match_id, game_creation, lp_before, lp_after, lp_delta,
+         tier_before, division_before, tier_after, division_after, exact)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+        ON CONFLICT (puuid, match_id) DO UPDATE
+        SET lp_before = EXCLUDED.lp_before,
+            lp_after = EXCLUDED.lp_after,
+            lp_delta = EXCLUDED.lp_delta,
+            tier_before = EXCLUDED.tier_before,
+            division_before = EXCLUDED.division_before,
+            tier_after = EXCLUDED.tier_after,
+            division_after = EXCLUDED.division_after,
+            exact = EXCLUDED.exact,
+            computed_at = now()
+        WHERE NOT public.match_rank_progress.exact
+      `,
+          [
+            puuid,
+            step.match_id,
+            step.game_creation,
+            step.lp_before,
+            step.lp_after,
+            step.lp_delta,
+            step.tier_before,
+            step.division_before,
+            step.tier_after,
+            step.division_after,
+            step.exact,
+          ],
+        );
+      }
+    });
+
+    res.json({ success: true });
   }),
 );
 
-// GET /db/match-summary/:matchId/:puuid/:ddragonVersion - summarize_match_from_db
+// GET /db/role-games/:puuid?mapId=&queueIds= - games and champion roles for
+// get_role_stats, which infers missing roles and aggregates in the app
 router.get(
-  "/match-summary/:matchId/:puuid/:ddragonVersion",
+  "/role-games/:puuid",
   asyncHandler(async (req, res) => {
-    const { matchId, puuid, ddragonVersion } = req.params;
+    const { puuid } = req.params;
+    const mapId = parseInt(req.query.mapId, 10) || 11;
+    const queueIds = parseQueueIds(req.query.queueIds);
 
-    console.log(
-      `[Database] ➡️  GET /match-summary/${matchId}/${puuid}/${ddragonVersion}`,
+    const games = await queryMany(
+      `
+    SELECT mp.team_position, mp.champion_name,
+           (SELECT string_agg(o.team_position, ',')
+            FROM public.match_participants o
+            WHERE o.mid = mp.mid AND o.team_id = mp.team_id AND o.puuid <> mp.puuid
+           ) AS teammate_positions,
+           mp.kills, mp.deaths, mp.assists, mp.cs, mp.gold_earned, mp.vision_score, mp.win,
+           md."gameDuration" AS game_duration,
+           (SELECT SUM(o.kills)
+            FROM public.match_participants o
+            WHERE o.mid = mp.mid AND o.team_id = mp.team_id
+           ) AS team_kills
+    FROM public.match_participants mp
+    JOIN public.match_details md ON md.mid = mp.mid
+    WHERE mp.puuid = $1
+      AND md."mapName" = $2
+      AND COALESCE(md."gameDuration",0) >= 300
+      AND ($3::int[] IS NULL OR md."queueType" = ANY($3))
+  `,
+      [puuid, mapId, queueIds],
     );
 
-    const row = await queryOne(
+    const champions = [
+      ...new Set(
+        games
+          .filter((g) => g.team_position === null && g.champion_name !== null)
+          .map((g) => g.champion_name),
+      ),
+    ];
+    const championRoles = champions.length
+      ? await queryMany(
+          `
+    SELECT champion_name, team_position, COUNT(*) AS games
+    FROM public.match_participants
+    WHERE champion_name = ANY($1)
+      AND team_position IS NOT NULL
+    GROUP BY champion_name, team_position
+  `,
+          [champions],
+        )
+      : [];
+
+    // BIGINT columns come back as strings
+    const toInt = (v) => (v === null ? null : parseInt(v, 10));
+    res.json({
+      games: games.map((g) => ({ ...g, team_kills: toInt(g.team_kills) })),
+      champion_roles: championRoles.map((c) => ({ ...c, games: toInt(c.games) })),
+    });
+  }),
+);
+
+// GET /db/matchups/:puuid?minGames=&queueIds= - get_matchup_stats
+// minGames is inclusive, like for /teammates
+router.get(
+  "/matchups/:puuid",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+    const minGames = parseInt(req.query.minGames, 10) || 1;
+
+    const matchups = await queryMany(
       `
-    SELECT participants, "gameCreation", "gameDuration", "queueType"
-    FROM public.match_details
-    WHERE "matchId" = $1
+    SELECT me.champion_name,
+           opp.champion_name AS opponent_champion_name,
+           me.team_position AS role,
+           COUNT(*)::int AS games,
+           COUNT(*) FILTER (WHERE me.win)::int AS wins,
+           (100.0 * COUNT(*) FILTER (WHERE me.win) / COUNT(*))::real AS winrate,
+           AVG((me.kills + me.assists)::real / GREATEST(me.deaths, 1)
+               - (opp.kills + opp.assists)::real / GREATEST(opp.deaths, 1))::real AS avg_kda_diff,
+           AVG(me.gold_earned - opp.gold_earned)::real AS avg_gold_diff
+    FROM public.match_participants me
+    JOIN public.match_details md ON md.mid = me.mid
+    JOIN public.match_participants opp
+      ON opp.mid = me.mid
+     AND opp.team_id <> me.team_id
+     AND opp.team_position = me.team_position
+    WHERE me.puuid = $1
+      AND me.champion_name IS NOT NULL
+      AND opp.champion_name IS NOT NULL
+      AND COALESCE(md."gameDuration",0) >= 300
+      AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+    GROUP BY me.champion_name, opp.champion_name, me.team_position
+    HAVING COUNT(*) >= $3
+    ORDER BY me.champion_name, games DESC, opp.champion_name
   `,
-      [matchId],
+      [puuid, parseQueueIds(req.query.queueIds), minGames],
     );
 
-    console.log(
-      `[Database] 📄 Match details query result: ${row ? "found" : "not found"}`,
+    res.json(matchups);
+  }),
+);
+
+// GET /db/teammates/:puuid?minGames=&queueIds= - get_teammates
+// minGames is inclusive: players seen together exactly that many times are kept
+router.get(
+  "/teammates/:puuid",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+    const minGames = parseInt(req.query.minGames, 10) || 1;
+
+    const teammates = await queryMany(
+      `
+    WITH games AS (
+        SELECT mp.mid, mp.team_id, mp.win, md."gameCreation" AS game_creation
+        FROM public.match_participants mp
+        JOIN public.match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND COALESCE(md."gameDuration",0) >= 300
+          AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+    ),
+    totals AS (
+        SELECT COUNT(*)::int AS games, COUNT(*) FILTER (WHERE win)::int AS wins
+        FROM games
+    ),
+    mates AS (
+        SELECT mate.puuid,
+               COUNT(*)::int AS games,
+               COUNT(*) FILTER (WHERE g.win)::int AS wins,
+               (ARRAY_AGG(g.mid ORDER BY g.game_creation DESC NULLS LAST))[1] AS last_mid
+        FROM games g
+        JOIN public.match_participants mate
+          ON mate.mid = g.mid
+         AND mate.team_id = g.team_id
+         AND mate.puuid <> $1
+        GROUP BY mate.puuid
+        HAVING COUNT(*) >= $3
+    )
+    SELECT m.puuid,
+           m.games,
+           m.wins,
+           totals.games AS total_games,
+           totals.wins AS total_wins,
+           (
+               SELECT NULLIF(p->>'riotIdGameName', '') || '#' || NULLIF(p->>'riotIdTagline', '')
+               FROM public.match_details md
+               CROSS JOIN LATERAL jsonb_array_elements(md.participants) p
+               WHERE md.mid = m.last_mid AND p->>'puuid' = m.puuid
+           ) AS riot_id
+    FROM mates m
+    CROSS JOIN totals
+    ORDER BY m.games DESC, m.wins DESC, m.puuid
+  `,
+      [puuid, parseQueueIds(req.query.queueIds), minGames],
     );
 
-    if (!row || !row.participants) {
-      console.log(`[Database] ❌ No match data found for ${matchId}`);
-      return res.status(404).json({
-        error: `No participants JSON in DB for match ${matchId}`,
-        statusCode: 404,
+    res.json(teammates);
+  }),
+);
+
+/**
+ * Build the MatchSummary the app shows for `participant` in a match_details row
+ */
+function buildMatchSummary(matchId, row, participant, ddragonVersion) {
+  const p = participant;
+  const cs = (p.totalMinionsKilled || 0) + (p.neutralMinionsKilled || 0);
+  const kda =
+    p.deaths === 0 ? p.kills + p.assists : (p.kills + p.assists) / p.deaths;
+  const champIcon = `https://ddragon.leagueoflegends.com/cdn/${ddragonVersion}/img/champion/${p.championName}.png`;
+  const items = [p.item0, p.item1, p.item2, p.item3, p.item4, p.item5];
+  const durationS = row.gameDuration || 0;
+  const minutes = durationS / 60.0;
+  const gpm = minutes > 0 ? p.goldEarned / minutes : 0;
+  const csPerMin = minutes > 0 ? cs / minutes : 0;
+  const visionPerMin = minutes > 0 ? p.visionScore / minutes : 0;
+
+  return {
+    match_id: matchId,
+    queue_id: parseInt(row.queueType) || 0,
+    game_creation_ms:
+      typeof row.gameCreation === "string"
+        ? parseInt(row.gameCreation)
+        : row.gameCreation || 0,
+    game_duration_s: parseInt(durationS) || 0,
+    win: Boolean(p.win),
+    champion_name: String(p.championName || ""),
+    champion_icon_url: champIcon,
+    kills: parseInt(p.kills) || 0,
+    deaths: parseInt(p.deaths) || 0,
+    assists: parseInt(p.assists) || 0,
+    cs: parseInt(cs) || 0,
+    kda: Math.round(kda * 100) / 100,
+    role: String(p.teamPosition || ""),
+    gold_earned: parseInt(p.goldEarned) || 0,
+    gpm: Math.round(gpm * 100) / 100,
+    cs_per_min: Math.round(csPerMin * 100) / 100,
+    vision_per_min: Math.round(visionPerMin * 100) / 100,
+    items: items.map((item) => parseInt(item) || 0),
+    trinket: parseInt(p.item6) || 0,
+    damage_dealt: parseInt(p.totalDamageDealtToChampions) || 0,
+    damage_taken: parseInt(p.totalDamageTaken) || 0,
+    vision_score: parseInt(p.visionScore) || 0,
+    kill_participation: parseFloat(
+      p.challenges ? p.challenges.killParticipation || 0.0 : 0.0,
+    ),
+    turret_takedowns: parseInt(p.turretTakedowns) || 0,
+    dragon_kills: parseInt(p.dragonKills) || 0,
+    baron_kills: parseInt(p.baronKills) || 0,
+    ddragon_version: ddragonVersion,
+  };
+}
+
+// POST /db/match-summaries - summarize_matches_from_db
+// Returns summaries in the order of `matchIds`, leaving out matches that are not
+// stored or that `puuid` did not play in.
+router.post(
+  "/match-summaries",
+  asyncHandler(async (req, res) => {
+    const { matchIds, puuid, ddragonVersion } = req.body;
+
+    if (!Array.isArray(matchIds) || !puuid || !ddragonVersion) {
+      return res.status(400).json({
+        error: "Missing required fields: matchIds, puuid, ddragonVersion",
+        statusCode: 400,
       });
     }
 
     console.log(
-      `[Database] Found match ${matchId}: ${row.participants.length} participants, duration: ${row.gameDuration}s, queue: ${row.queueType}`,
-    );
-    console.log(
-      `[Database] Raw data types - gameCreation: ${typeof row.gameCreation} (${row.gameCreation}), gameDuration: ${typeof row.gameDuration} (${row.gameDuration})`,
+      `[Database] ➡️  POST /match-summaries for ${matchIds.length} matches of ${puuid}`,
     );
-    console.log(`[Database] Looking for participant with puuid: ${puuid}`);
-    const participants = row.participants;
-    console.log(`[Database] Looking for participant with puuid: ${puuid}`);
 
-    const participant = participants.find((p) => p.puuid === puuid);
+    const rows = await queryMany(
+      `
+    SELECT "matchId", participants, "gameCreation", "gameDuration", "queueType"
+    FROM public.match_details
+    WHERE "matchId" = ANY($1)
+  `,
+      [matchIds],
+    );
+    const byId = new Map(rows.map((row) => [row.matchId, row]));
 
-    if (!participant) {
-      console.log(
-        `[Database] ❌ Participant ${puuid} not found in match ${matchId}`,
-      );
-      console.log(
-        `[Database] Available PUUIDs: [${participants.map((p) => p.puuid).join(", ")}]`,
+    const summaries = [];
+    for (const matchId of matchIds) {
+      const row = byId.get(matchId);
+      const participant = row?.participants?.find((p) => p.puuid === puuid);
+      if (!participant) {
+        console.log(
+          `[Database] ❌ No participant ${puuid} stored for match ${matchId}`,
+        );
+        continue;
+      }
+      summaries.push(
+        buildMatchSummary(matchId, row, participant, ddragonVersion),
       );
-      return res.status(404).json({
-        error: `Participant ${puuid} not found in match ${matchId}`,
-        statusCode: 404,
-      });
     }
 
     console.log(
-      `[Database] ✅ Found participant: ${participant.championName} (${participant.kills}/${participant.deaths}/${participant.assists})`,
-    );
-    console.log(`[Database] Building match summary...`);
-
-    const p = participant;
-    const cs = (p.totalMinionsKilled || 0) + (p.neutralMinionsKilled || 0);
-    const kda =
-      p.deaths === 0 ? p.kills + p.assists : (p.kills + p.assists) / p.deaths;
-    const champIcon = `https://ddragon.leagueoflegends.com/cdn/${ddragonVersion}/img/champion/${p.championName}.png`;
-    const items = [p.item0, p.item1, p.item2, p.item3, p.item4, p.item5];
-    const durationS = row.gameDuration || 0;
-    const minutes = durationS / 60.0;
-    const gpm = minutes > 0 ? p.goldEarned / minutes : 0;
-    const csPerMin = minutes > 0 ? cs / minutes : 0;
-    const visionPerMin = minutes > 0 ? p.visionScore / minutes : 0;
-
-    const matchSummary = {
-      match_id: matchId,
-      queue_id: parseInt(row.queueType) || 0,
-      game_creation_ms:
-        typeof row.gameCreation === "string"
-          ? parseInt(row.gameCreation)
-          : row.gameCreation || 0,
-      game_duration_s: parseInt(durationS) || 0,
-      win: Boolean(p.win),
-      champion_name: String(p.championName || ""),
-      champion_icon_url: champIcon,
-      kills: parseInt(p.kills) || 0,
-      deaths: parseInt(p.deaths) || 0,
-      assists: parseInt(p.assists) || 0,
-      cs: parseInt(cs) || 0,
-      kda: Math.round(kda * 100) / 100,
-      role: String(p.teamPosition || ""),
-      gold_earned: parseInt(p.goldEarned) || 0,
-      gpm: Math.round(gpm * 100) / 100,
-      cs_per_min: Math.round(csPerMin * 100) / 100,
-      vision_per_min: Math.round(visionPerMin * 100) / 100,
-      items: items.map((item) => parseInt(item) || 0),
-      trinket: parseInt(p.item6) || 0,
-      damage_dealt: parseInt(p.totalDamageDealtToChampions) || 0,
-      damage_taken: parseInt(p.totalDamageTaken) || 0,
-      vision_score: parseInt(p.visionScore) || 0,
-      kill_participation: parseFloat(
-        p.challenges ? p.challenges.killParticipation || 0.0 : 0.0,
-      ),
-      turret_takedowns: parseInt(p.turretTakedowns) || 0,
-      dragon_kills: parseInt(p.dragonKills) || 0,
-      baron_kills: parseInt(p.baronKills) || 0,
-      win: Boolean(p.win),
-      ddragon_version: ddragonVersion,
-    };
-
-    console.log(
-      `[Database] ⬅️  GET /match-summary/${matchId} response: ${matchSummary.champion_name} ${matchSummary.win ? "WIN" : "LOSS"} (${matchSummary.kda} KDA)`,
+      `[Database] ⬅️  POST /match-summaries response: ${summaries.length} summaries`,
     );
-    res.json(matchSummary);
+    res.json(summaries);
   }),
 );
 
@@ -990,4 +1568,116 @@ router.get(
   }),
 );
 
+// GET /db/schema-version - schema_version
+router.get(
+  "/schema-version",
+  asyncHandler(async (req, res) => {
+    const tracked = await queryScalar(
+      `SELECT to_regclass('public._sqlx_migrations') IS NOT NULL`,
+    );
+    if (!tracked) {
+      return res.json({ version: null });
+    }
+
+    const version = await queryScalar(
+      `SELECT MAX(version) FROM public._sqlx_migrations WHERE success`,
+    );
+    // BIGINT comes back as a string
+    res.json({ version: version === null ? null : parseInt(version, 10) });
+  }),
+);
+
+// GET /db/backfill/:puuid - get_backfill_checkpoint
+router.get(
+  "/backfill/:puuid",
+  asyncHandler(async (req, res) => {
+    const { puuid } = req.params;
+
+    const checkpoint = await queryOne(
+      `
+    SELECT puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+           oldest_game_creation, matches_fetched, matches_skipped, completed
+    FROM public.backfill_checkpoints
+    WHERE puuid = $1
+  `,
+      [puuid],
+    );
+
+    // BIGINT columns come back as strings
+    if (checkpoint) {
+      checkpoint.anchor_time = parseInt(checkpoint.anchor_time, 10);
+      checkpoint.cutoff_time =
+        checkpoint.cutoff_time === null
+          ? null
+          : parseInt(checkpoint.cutoff_time, 10);
+      checkpoint.oldest_game_creation =
+        checkpoint.oldest_game_creation === null
+          ? null
+          : parseInt(checkpoint.oldest_game_creation, 10);
+    }
+
+    res.json(checkpoint);
+  }),
+);
+
+// PUT /db/backfill - save_backfill_checkpoint
+router.put(
+  "/backfill",
+  asyncHandler(async (req, res) => {
+    const {
+      puuid,
+      regional,
+      anchor_time,
+      cutoff_time,
+      max_matches,
+      next_start,
+      oldest_game_creation,
+      matches_fetched,
+      matches_skipped,
+      completed,
+    } = req.body;
+
+    if (!puuid || !regional || anchor_time === undefined) {
+      return res.status(400).json({
+        error: "Missing required fields: puuid, regional, anchor_time",
+        statusCode: 400,
+      });
+    }
+
+    await queryOne(
+      `
+    INSERT INTO public.backfill_checkpoints
+    (puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+     oldest_game_creation, matches_fetched, matches_skipped, completed, updated_at)
+    VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10, now())
+    ON CONFLICT (puuid) DO UPDATE
+    SET regional = EXCLUDED.regional,
+        anchor_time = EXCLUDED.anchor_time,
+        cutoff_time = EXCLUDED.cutoff_time,
+        max_matches = EXCLUDED.max_matches,
+        next_start = EXCLUDED.next_start,
+        oldest_game_creation = EXCLUDED.oldest_game_creation,
+        matches_fetched = EXCLUDED.matches_fetched,
+        matches_skipped = EXCLUDED.matches_skipped,
+        completed = EXCLUDED.completed,
+        updated_at = now()
+  `,
+      [
+        puuid,
+        regional,
+        anchor_time,
+        cutoff_time ?? null,
+        max_matches ?? null,
+        next_start || 0,
+        oldest_game_creation ?? null,
+        matches_fetched || 0,
+        matches_skipped || 0,
+        Boolean(completed),
+      ],
+    );
+
+    res.json({ success: true });
+  }),
+);
+
 module.exports = router;
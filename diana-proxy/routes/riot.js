@@ -28,6 +28,7 @@ router.get(
         base: regionalBase,
         path: `/riot/account/v1/accounts/by-riot-id/${encodeURIComponent(name)}/${encodeURIComponent(tag)}`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -57,6 +58,7 @@ router.get(
         base: regionalBase,
         path: `/riot/account/v1/accounts/by-puuid/${puuid}`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -86,6 +88,7 @@ router.get(
         base: platformBase,
         path: `/lol/summoner/v4/summoners/by-puuid/${puuid}`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -115,6 +118,7 @@ router.get(
         base: platformBase,
         path: `/lol/league/v4/entries/by-puuid/${puuid}`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -144,6 +148,7 @@ router.get(
         base: regionalBase,
         path: `/lol/match/v5/matches/by-puuid/${puuid}/ids`,
         query: otherQuery,
+        res,
       });
 
       res.json(data);
@@ -173,6 +178,7 @@ router.get(
         base: regionalBase,
         path: `/lol/match/v5/matches/${matchId}`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -202,6 +208,7 @@ router.get(
         base: regionalBase,
         path: `/lol/match/v5/matches/${matchId}/timeline`,
         query: {},
+        res,
       });
 
       res.json(data);
@@ -257,6 +264,7 @@ router.post(
             base: regionalBase,
             path: `/riot/account/v1/accounts/by-puuid/${puuid}`,
             query: {},
+            res,
           });
 
           const displayName = data.tagLine
@@ -266,6 +274,10 @@ router.post(
 
           await new Promise((resolve) => setTimeout(resolve, 100));
         } catch (error) {
+          // Let the client back off and retry the batch instead of returning placeholders
+          if (error.statusCode === 429) {
+            throw error;
+          }
           console.error(
             `Failed to fetch account for PUUID ${puuid}:`,
             error.message,
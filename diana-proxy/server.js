@@ -7,13 +7,15 @@ const morgan = require("morgan");
 const riotRoutes = require("./routes/riot");
 const databaseRoutes = require("./routes/database");
 const { testConnection } = require("./utils/database");
+const { migrate } = require("./utils/migrate");
 
 const app = express();
 const PORT = process.env.PORT || 4000;
 
 app.use(cors());
 app.use(morgan("dev"));
-app.use(express.json());
+// Atomic ingests carry a whole match-v5 timeline, which runs to several MB
+app.use(express.json({ limit: process.env.JSON_BODY_LIMIT || "25mb" }));
 
 app.use((req, res, next) => {
   const start = Date.now();
@@ -77,6 +79,12 @@ app.listen(PORT, async () => {
   const dbConnected = await testConnection();
   if (dbConnected) {
     console.log(`✅ Database connection successful`);
+    try {
+      const version = await migrate();
+      console.log(`✅ Database schema at version ${version}`);
+    } catch (error) {
+      console.error(`❌ Migrations failed:`, error);
+    }
   } else {
     console.log(
       `⚠️  Database connection failed - database endpoints will not work`,
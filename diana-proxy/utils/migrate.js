@@ -0,0 +1,124 @@
+const crypto = require("crypto");
+const fs = require("fs");
+const path = require("path");
+const zlib = require("zlib");
+
+const { getPool } = require("./database");
+
+// The app's Postgres migrations, mounted at MIGRATIONS_DIR in docker-compose
+const MIGRATIONS_DIR =
+  process.env.MIGRATIONS_DIR ||
+  path.join(__dirname, "..", "..", "src-tauri", "migrations");
+
+/**
+ * Migration files as sqlx reads them: `<version>_<description>.sql`, with the
+ * description's underscores turned into spaces and a SHA-384 checksum of the file
+ */
+function readMigrations(dir) {
+  return fs
+    .readdirSync(dir, { withFileTypes: true })
+    .filter((entry) => entry.isFile() && /^\d+_.+\.sql$/.test(entry.name))
+    .map((entry) => {
+      const [version, rest] = entry.name.split(/_(.*)/s);
+      const sql = fs.readFileSync(path.join(dir, entry.name), "utf8");
+      return {
+        version: BigInt(version),
+        description: rest.slice(0, -".sql".length).replace(/_/g, " "),
+        sql,
+        checksum: crypto.createHash("sha384").update(sql).digest(),
+      };
+    })
+    .sort((a, b) => (a.version < b.version ? -1 : 1));
+}
+
+/**
+ * Same advisory lock sqlx takes, so the proxy and the app's Postgres store never
+ * migrate one database at the same time
+ */
+function lockId(databaseName) {
+  return (
+    BigInt(0x3d32ad9e) * BigInt(zlib.crc32(Buffer.from(databaseName)))
+  ).toString();
+}
+
+/**
+ * Applies the migrations missing from `_sqlx_migrations`, each in its own
+ * transaction. Databases first set up by the old docker-compose init scripts have
+ * no such table, so every migration runs once more; they are all re-runnable.
+ */
+async function migrate() {
+  const migrations = readMigrations(MIGRATIONS_DIR);
+  const client = await getPool().connect();
+
+  try {
+    const {
+      rows: [{ name }],
+    } = await client.query("SELECT current_database() AS name");
+    const lock = lockId(name);
+    await client.query("SELECT pg_advisory_lock($1)", [lock]);
+
+    try {
+      await client.query(`
+        CREATE TABLE IF NOT EXISTS _sqlx_migrations (
+          version BIGINT PRIMARY KEY,
+          description TEXT NOT NULL,
+          installed_on TIMESTAMPTZ NOT NULL DEFAULT now(),
+          success BOOLEAN NOT NULL,
+          checksum BYTEA NOT NULL,
+          execution_time BIGINT NOT NULL
+        )
+      `);
+
+      const { rows } = await client.query(
+        "SELECT version, success FROM _sqlx_migrations",
+      );
+      const applied = new Map(rows.map((r) => [BigInt(r.version), r.success]));
+      const dirty = rows.find((r) => !r.success);
+      if (dirty) {
+        throw new Error(
+          `Migration ${dirty.version} is partially applied, fix it by hand`,
+        );
+      }
+
+      for (const migration of migrations) {
+        if (applied.has(migration.version)) {
+          continue;
+        }
+
+        const start = process.hrtime.bigint();
+        await client.query("BEGIN");
+        try {
+          await client.query(migration.sql);
+          await client.query(
+            `
+            INSERT INTO _sqlx_migrations
+            (version, description, success, checksum, execution_time)
+            VALUES ($1, $2, TRUE, $3, $4)
+          `,
+            [
+              migration.version.toString(),
+              migration.description,
+              migration.checksum,
+              (process.hrtime.bigint() - start).toString(),
+            ],
+          );
+          await client.query("COMMIT");
+        } catch (error) {
+          await client.query("ROLLBACK");
+          throw error;
+        }
+        console.log(
+          `[Database] Applied migration ${migration.version} (${migration.description})`,
+        );
+      }
+    } finally {
+      await client.query("SELECT pg_advisory_unlock($1)", [lock]);
+    }
+  } finally {
+    client.release();
+  }
+
+  return migrations.length ? migrations[migrations.length - 1].version : null;
+}
+
+module.exports = { migrate, MIGRATIONS_DIR };
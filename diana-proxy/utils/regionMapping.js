@@ -15,13 +15,20 @@ const PLATFORM_MAP = {
   euw1: "euw1",
   eun1: "eun1",
   tr1: "tr1",
-  ru1: "ru1",
+  ru: "ru",
+  ru1: "ru",
+  me1: "me1",
   kr: "kr",
   jp1: "jp1",
   oc1: "oc1",
+  ph2: "ph2",
+  sg2: "sg2",
+  th2: "th2",
+  tw2: "tw2",
+  vn2: "vn2",
 };
 
-// Map region codes to their regional and platform identifiers
+// Map region codes to their platform and match-v5 regional identifiers
 const REGION_TO_BASES = {
   EUW: { platform: "euw1", regional: "europe" },
   EUW1: { platform: "euw1", regional: "europe" },
@@ -42,8 +49,20 @@ const REGION_TO_BASES = {
   OC1: { platform: "oc1", regional: "sea" },
   TR: { platform: "tr1", regional: "europe" },
   TR1: { platform: "tr1", regional: "europe" },
-  RU: { platform: "ru1", regional: "europe" },
-  RU1: { platform: "ru1", regional: "europe" },
+  RU: { platform: "ru", regional: "europe" },
+  RU1: { platform: "ru", regional: "europe" },
+  ME: { platform: "me1", regional: "europe" },
+  ME1: { platform: "me1", regional: "europe" },
+  PH: { platform: "ph2", regional: "sea" },
+  PH2: { platform: "ph2", regional: "sea" },
+  SG: { platform: "sg2", regional: "sea" },
+  SG2: { platform: "sg2", regional: "sea" },
+  TH: { platform: "th2", regional: "sea" },
+  TH2: { platform: "th2", regional: "sea" },
+  TW: { platform: "tw2", regional: "sea" },
+  TW2: { platform: "tw2", regional: "sea" },
+  VN: { platform: "vn2", regional: "sea" },
+  VN2: { platform: "vn2", regional: "sea" },
 };
 
 /**
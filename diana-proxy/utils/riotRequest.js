@@ -1,14 +1,41 @@
 const axios = require("axios");
 
+// Rate limit headers Riot sends back, relayed so the desktop client can pace itself
+const RATE_LIMIT_HEADERS = [
+  "x-app-rate-limit",
+  "x-app-rate-limit-count",
+  "x-method-rate-limit",
+  "x-method-rate-limit-count",
+  "x-rate-limit-type",
+  "retry-after",
+];
+
+/**
+ * Copy Riot rate limit headers onto an Express response
+ * @param {Object} res - Express response
+ * @param {Object} headers - Upstream response headers
+ */
+function relayRateLimitHeaders(res, headers) {
+  if (!res || !headers) {
+    return;
+  }
+  for (const name of RATE_LIMIT_HEADERS) {
+    if (headers[name] !== undefined) {
+      res.set(name, headers[name]);
+    }
+  }
+}
+
 /**
  * Forward a request to the Riot API with authentication
  * @param {Object} params - Request parameters
  * @param {string} params.base - Base URL (e.g., 'euw1', 'americas')
  * @param {string} params.path - API path (e.g., '/riot/account/v1/accounts/by-puuid/...')
  * @param {Object} params.query - Query parameters object
+ * @param {Object} [params.res] - Express response to relay rate limit headers to
  * @returns {Promise<any>} - Riot API response data
  */
-async function forward({ base, path, query = {} }) {
+async function forward({ base, path, query = {}, res }) {
   if (!process.env.RIOT_API_KEY) {
     throw new Error("RIOT_API_KEY environment variable not set");
   }
@@ -29,8 +56,10 @@ async function forward({ base, path, query = {} }) {
       timeout: 30000,
     });
 
+    relayRateLimitHeaders(res, response.headers);
     return response.data;
   } catch (error) {
+    relayRateLimitHeaders(res, error.response?.headers);
     console.error(
       `[RiotAPI] Error for ${fullUrl}:`,
       error.response?.status,
@@ -84,4 +113,5 @@ async function forwardExternal(url) {
 module.exports = {
   forward,
   forwardExternal,
+  relayRateLimitHeaders,
 };
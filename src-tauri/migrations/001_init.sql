@@ -1,16 +1,4 @@
-DROP INDEX IF EXISTS "idx_match_details_entryPlayerPuuid";
-DROP INDEX IF EXISTS "idx_match_timeline_mid";
-DROP INDEX IF EXISTS "idx_match_details_participants";
-DROP INDEX IF EXISTS "idx_match_details_teams";
-DROP INDEX IF EXISTS "idx_match_timeline_events";
-DROP INDEX IF EXISTS "idx_match_timeline_participantFrames";
-
-DROP TABLE IF EXISTS "match_timeline" CASCADE;
-DROP TABLE IF EXISTS "match_details" CASCADE;
-DROP TABLE IF EXISTS "summoners" CASCADE;
-DROP TABLE IF EXISTS "regions" CASCADE;
-
-CREATE TABLE public.summoners (
+CREATE TABLE IF NOT EXISTS public.summoners (
     puuid                  varchar(200) PRIMARY KEY,
     "gameName"             varchar(100) NOT NULL,
     "tagLine"              varchar(10)  NOT NULL,
@@ -27,7 +15,7 @@ CREATE TABLE public.summoners (
     "lastMissingDataNotification" timestamptz NOT NULL DEFAULT now()
 );
 
-CREATE TABLE public.match_details (
+CREATE TABLE IF NOT EXISTS public.match_details (
     mid                    bigserial PRIMARY KEY,
     "matchId"              varchar(50)  NOT NULL UNIQUE,
     "entryPlayerPuuid"     varchar(200) NOT NULL REFERENCES public.summoners(puuid) ON DELETE CASCADE,
@@ -45,7 +33,7 @@ CREATE TABLE public.match_details (
     "lastUpdated"          timestamptz  NOT NULL DEFAULT now()
 );
 
-CREATE TABLE public.match_timeline (
+CREATE TABLE IF NOT EXISTS public.match_timeline (
     tid                    bigserial PRIMARY KEY,
     mid                    bigint       NOT NULL REFERENCES public.match_details(mid) ON DELETE CASCADE,
     "entryParticipantId"   varchar(200) NOT NULL,
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS public.backfill_checkpoints (
+  puuid                 TEXT PRIMARY KEY,
+  regional              TEXT NOT NULL,
+  anchor_time           BIGINT NOT NULL,
+  cutoff_time           BIGINT,
+  max_matches           INTEGER,
+  next_start            INTEGER NOT NULL DEFAULT 0,
+  oldest_game_creation  BIGINT,
+  matches_fetched       INTEGER NOT NULL DEFAULT 0,
+  completed             BOOLEAN NOT NULL DEFAULT FALSE,
+  updated_at            TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_backfill_checkpoints_pending
+  ON public.backfill_checkpoints(completed) WHERE completed = FALSE;
@@ -0,0 +1,42 @@
+-- One row per player per match, so per-player aggregates no longer unpack
+-- match_details.participants. Filled at ingest, backfilled here from the JSONB.
+CREATE TABLE IF NOT EXISTS public.match_participants (
+  mid                  BIGINT NOT NULL REFERENCES public.match_details(mid) ON DELETE CASCADE,
+  puuid                VARCHAR(200) NOT NULL,
+  team_id              INTEGER,
+  champion_name        TEXT,
+  team_position        TEXT,
+  kills                INTEGER NOT NULL DEFAULT 0,
+  deaths               INTEGER NOT NULL DEFAULT 0,
+  assists              INTEGER NOT NULL DEFAULT 0,
+  cs                   INTEGER NOT NULL DEFAULT 0,
+  gold_earned          INTEGER NOT NULL DEFAULT 0,
+  damage_to_champions  INTEGER NOT NULL DEFAULT 0,
+  vision_score         INTEGER NOT NULL DEFAULT 0,
+  win                  BOOLEAN NOT NULL,
+  PRIMARY KEY (mid, puuid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_match_participants_puuid ON public.match_participants(puuid);
+
+INSERT INTO public.match_participants
+(mid, puuid, team_id, champion_name, team_position, kills, deaths, assists, cs,
+ gold_earned, damage_to_champions, vision_score, win)
+SELECT
+  md.mid,
+  p->>'puuid',
+  (p->>'teamId')::int,
+  p->>'championName',
+  NULLIF(p->>'teamPosition', ''),
+  COALESCE((p->>'kills')::int, 0),
+  COALESCE((p->>'deaths')::int, 0),
+  COALESCE((p->>'assists')::int, 0),
+  COALESCE((p->>'totalMinionsKilled')::int, 0) + COALESCE((p->>'neutralMinionsKilled')::int, 0),
+  COALESCE((p->>'goldEarned')::int, 0),
+  COALESCE((p->>'totalDamageDealtToChampions')::int, 0),
+  COALESCE((p->>'visionScore')::int, 0),
+  COALESCE((p->>'win')::boolean, false)
+FROM public.match_details md
+JOIN LATERAL jsonb_array_elements(md.participants) p ON true
+WHERE p->>'puuid' IS NOT NULL
+ON CONFLICT (mid, puuid) DO NOTHING;
@@ -0,0 +1,12 @@
+-- Solo queue rank each time it is fetched from Riot. Two snapshots with a single
+-- ranked game between them give that game's exact LP change.
+CREATE TABLE IF NOT EXISTS public.rank_snapshots (
+  id          BIGSERIAL PRIMARY KEY,
+  puuid       VARCHAR(200) NOT NULL,
+  tier        TEXT NOT NULL,
+  division    TEXT NOT NULL,
+  lp          INTEGER NOT NULL,
+  taken_at    BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rank_snapshots_puuid_taken ON public.rank_snapshots(puuid, taken_at);
@@ -0,0 +1,4 @@
+-- player_summary.streak now holds the current streak (positive for wins, negative
+-- for losses), these hold the longest ones of the season.
+ALTER TABLE public.player_summary ADD COLUMN IF NOT EXISTS longest_win_streak INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE public.player_summary ADD COLUMN IF NOT EXISTS longest_loss_streak INTEGER NOT NULL DEFAULT 0;
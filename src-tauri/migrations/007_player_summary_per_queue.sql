@@ -0,0 +1,6 @@
+-- Summaries are cached per queue group ('ranked_solo', 'aram', 'all', ...) instead of
+-- once per player. The group replaces queue_id, which only ever held 420.
+ALTER TABLE public.player_summary ADD COLUMN IF NOT EXISTS queue TEXT NOT NULL DEFAULT 'ranked_solo';
+ALTER TABLE public.player_summary DROP CONSTRAINT IF EXISTS player_summary_pkey;
+ALTER TABLE public.player_summary ADD PRIMARY KEY (puuid, queue);
+ALTER TABLE public.player_summary DROP COLUMN IF EXISTS queue_id;
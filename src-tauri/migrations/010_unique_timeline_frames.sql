@@ -0,0 +1,9 @@
+-- A match ingested twice at once got its frames stored twice: the second insert
+-- gets the existing mid back and writes the frames again. Drop the copies and let
+-- the inserts' ON CONFLICT DO NOTHING skip them from now on.
+DELETE FROM public.match_timeline a
+USING public.match_timeline b
+WHERE a.mid = b.mid AND a."frameIndex" = b."frameIndex" AND a.tid > b.tid;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_match_timeline_mid_frame
+  ON public.match_timeline(mid, "frameIndex");
@@ -0,0 +1,3 @@
+-- Matches the backfill moved past because they fail the same way every time, like
+-- ones Riot no longer has.
+ALTER TABLE public.backfill_checkpoints ADD COLUMN IF NOT EXISTS matches_skipped INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,52 @@
+-- Same tables as the Postgres schema, for the embedded store. JSON columns hold
+-- JSON text and are queried with the json1 functions.
+
+CREATE TABLE IF NOT EXISTS summoners (
+    puuid                  TEXT PRIMARY KEY,
+    "gameName"             TEXT NOT NULL,
+    "tagLine"              TEXT NOT NULL,
+    region                 TEXT NOT NULL DEFAULT 'EU_WEST',
+    "matchRegionPrefix"    TEXT,
+    "deepLolLink"          TEXT,
+    tier                   TEXT NOT NULL DEFAULT 'UNRANKED',
+    rank                   TEXT,
+    lp                     INTEGER NOT NULL DEFAULT 0,
+    "currentMatchId"       TEXT,
+    "discordChannelId"     TEXT,
+    "regionGroup"          TEXT,
+    "lastUpdated"          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "lastMissingDataNotification" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS match_details (
+    mid                    INTEGER PRIMARY KEY AUTOINCREMENT,
+    "matchId"              TEXT NOT NULL UNIQUE,
+    "entryPlayerPuuid"     TEXT NOT NULL REFERENCES summoners(puuid) ON DELETE CASCADE,
+    "gameVersion"          TEXT,
+    "gameCreation"         INTEGER,
+    "gameStartTime"        INTEGER,
+    "gameEndTime"          INTEGER,
+    "gameDuration"         INTEGER,
+    "gameMode"             TEXT,
+    "gameType"             TEXT,
+    "queueType"            INTEGER,
+    "mapName"              INTEGER,
+    participants           TEXT,
+    teams                  TEXT,
+    "lastUpdated"          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS match_timeline (
+    tid                    INTEGER PRIMARY KEY AUTOINCREMENT,
+    mid                    INTEGER NOT NULL REFERENCES match_details(mid) ON DELETE CASCADE,
+    "entryParticipantId"   TEXT NOT NULL,
+    "frameIndex"           INTEGER,
+    "timestamp"            INTEGER,
+    "participantFrames"    TEXT,
+    events                 TEXT,
+    "lastUpdated"          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_match_details_entryPlayerPuuid ON match_details("entryPlayerPuuid");
+CREATE INDEX IF NOT EXISTS idx_match_details_gameCreation ON match_details("gameCreation" DESC);
+CREATE INDEX IF NOT EXISTS idx_match_timeline_mid ON match_timeline(mid);
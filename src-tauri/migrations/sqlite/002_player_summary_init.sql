@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS player_summary (
+  puuid           TEXT PRIMARY KEY,
+  computed_at     TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  queue_id        INTEGER NOT NULL DEFAULT 420,
+  games           INTEGER NOT NULL,
+  wins            INTEGER NOT NULL,
+  losses          INTEGER NOT NULL,
+  avg_kda         REAL NOT NULL,
+  winrate         REAL NOT NULL,
+  streak          INTEGER NOT NULL,
+  top_champs      TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS match_rank_progress (
+  puuid            TEXT NOT NULL,
+  match_id         TEXT NOT NULL,
+  game_creation    INTEGER NOT NULL,
+  lp_before        INTEGER NOT NULL,
+  lp_after         INTEGER NOT NULL,
+  lp_delta         INTEGER NOT NULL,
+  tier_before      TEXT NOT NULL,
+  division_before  TEXT NOT NULL,
+  tier_after       TEXT NOT NULL,
+  division_after   TEXT NOT NULL,
+  exact            BOOLEAN NOT NULL DEFAULT FALSE,
+  computed_at      TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (puuid, match_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_mrp_puuid_creation ON match_rank_progress(puuid, game_creation DESC);
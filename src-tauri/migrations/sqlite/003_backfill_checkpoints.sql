@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS backfill_checkpoints (
+  puuid                 TEXT PRIMARY KEY,
+  regional              TEXT NOT NULL,
+  anchor_time           INTEGER NOT NULL,
+  cutoff_time           INTEGER,
+  max_matches           INTEGER,
+  next_start            INTEGER NOT NULL DEFAULT 0,
+  oldest_game_creation  INTEGER,
+  matches_fetched       INTEGER NOT NULL DEFAULT 0,
+  completed             BOOLEAN NOT NULL DEFAULT FALSE,
+  updated_at            TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
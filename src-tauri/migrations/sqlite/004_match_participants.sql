@@ -0,0 +1,42 @@
+-- One row per player per match, so per-player aggregates no longer unpack
+-- match_details.participants. Filled at ingest, backfilled here from the JSON.
+CREATE TABLE IF NOT EXISTS match_participants (
+  mid                  INTEGER NOT NULL REFERENCES match_details(mid) ON DELETE CASCADE,
+  puuid                TEXT NOT NULL,
+  team_id              INTEGER,
+  champion_name        TEXT,
+  team_position        TEXT,
+  kills                INTEGER NOT NULL DEFAULT 0,
+  deaths               INTEGER NOT NULL DEFAULT 0,
+  assists              INTEGER NOT NULL DEFAULT 0,
+  cs                   INTEGER NOT NULL DEFAULT 0,
+  gold_earned          INTEGER NOT NULL DEFAULT 0,
+  damage_to_champions  INTEGER NOT NULL DEFAULT 0,
+  vision_score         INTEGER NOT NULL DEFAULT 0,
+  win                  BOOLEAN NOT NULL,
+  PRIMARY KEY (mid, puuid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_match_participants_puuid ON match_participants(puuid);
+
+INSERT INTO match_participants
+(mid, puuid, team_id, champion_name, team_position, kills, deaths, assists, cs,
+ gold_earned, damage_to_champions, vision_score, win)
+SELECT
+  md.mid,
+  json_extract(p.value, '$.puuid'),
+  json_extract(p.value, '$.teamId'),
+  json_extract(p.value, '$.championName'),
+  NULLIF(json_extract(p.value, '$.teamPosition'), ''),
+  COALESCE(json_extract(p.value, '$.kills'), 0),
+  COALESCE(json_extract(p.value, '$.deaths'), 0),
+  COALESCE(json_extract(p.value, '$.assists'), 0),
+  COALESCE(json_extract(p.value, '$.totalMinionsKilled'), 0)
+    + COALESCE(json_extract(p.value, '$.neutralMinionsKilled'), 0),
+  COALESCE(json_extract(p.value, '$.goldEarned'), 0),
+  COALESCE(json_extract(p.value, '$.totalDamageDealtToChampions'), 0),
+  COALESCE(json_extract(p.value, '$.visionScore'), 0),
+  COALESCE(json_extract(p.value, '$.win'), 0)
+FROM match_details md, json_each(md.participants) p
+WHERE json_extract(p.value, '$.puuid') IS NOT NULL
+ON CONFLICT (mid, puuid) DO NOTHING;
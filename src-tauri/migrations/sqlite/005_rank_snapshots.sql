@@ -0,0 +1,12 @@
+-- Solo queue rank each time it is fetched from Riot. Two snapshots with a single
+-- ranked game between them give that game's exact LP change.
+CREATE TABLE IF NOT EXISTS rank_snapshots (
+  id          INTEGER PRIMARY KEY AUTOINCREMENT,
+  puuid       TEXT NOT NULL,
+  tier        TEXT NOT NULL,
+  division    TEXT NOT NULL,
+  lp          INTEGER NOT NULL,
+  taken_at    INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rank_snapshots_puuid_taken ON rank_snapshots(puuid, taken_at);
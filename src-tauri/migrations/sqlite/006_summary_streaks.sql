@@ -0,0 +1,4 @@
+-- player_summary.streak now holds the current streak (positive for wins, negative
+-- for losses), these hold the longest ones of the season.
+ALTER TABLE player_summary ADD COLUMN longest_win_streak INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE player_summary ADD COLUMN longest_loss_streak INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,28 @@
+-- Summaries are cached per queue group ('ranked_solo', 'aram', 'all', ...) instead of
+-- once per player. The group replaces queue_id, which only ever held 420. SQLite can't
+-- change a primary key in place, so the table is rebuilt.
+CREATE TABLE player_summary_new (
+  puuid                TEXT NOT NULL,
+  queue                TEXT NOT NULL DEFAULT 'ranked_solo',
+  computed_at          TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  games                INTEGER NOT NULL,
+  wins                 INTEGER NOT NULL,
+  losses               INTEGER NOT NULL,
+  avg_kda              REAL NOT NULL,
+  winrate              REAL NOT NULL,
+  streak               INTEGER NOT NULL,
+  longest_win_streak   INTEGER NOT NULL DEFAULT 0,
+  longest_loss_streak  INTEGER NOT NULL DEFAULT 0,
+  top_champs           TEXT NOT NULL,
+  PRIMARY KEY (puuid, queue)
+);
+
+INSERT INTO player_summary_new
+(puuid, computed_at, games, wins, losses, avg_kda, winrate, streak,
+ longest_win_streak, longest_loss_streak, top_champs)
+SELECT puuid, computed_at, games, wins, losses, avg_kda, winrate, streak,
+       longest_win_streak, longest_loss_streak, top_champs
+FROM player_summary;
+
+DROP TABLE player_summary;
+ALTER TABLE player_summary_new RENAME TO player_summary;
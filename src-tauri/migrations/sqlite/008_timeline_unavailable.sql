@@ -0,0 +1,7 @@
+-- Matches whose timeline Riot does not have (404) or returns without frames. They
+-- are skipped by the timeline backfill instead of being retried on every sync.
+CREATE TABLE IF NOT EXISTS timeline_unavailable (
+  mid        INTEGER PRIMARY KEY REFERENCES match_details(mid) ON DELETE CASCADE,
+  reason     TEXT NOT NULL,
+  marked_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
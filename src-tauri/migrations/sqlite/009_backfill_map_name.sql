@@ -0,0 +1,7 @@
+-- Ingest did not store the map, so every match so far has a NULL "mapName" and the
+-- role breakdown (Summoner's Rift only) saw none of them. Fill it in for the queues
+-- that are only played on Summoner's Rift (mapId 11).
+UPDATE match_details
+SET "mapName" = 11
+WHERE "mapName" IS NULL
+  AND "queueType" IN (400, 420, 430, 440, 480, 490, 700, 830, 840, 850, 870, 880, 890);
@@ -0,0 +1,13 @@
+-- A match ingested twice at once got its frames stored twice: the second insert
+-- gets the existing mid back and writes the frames again. Drop the copies and let
+-- the inserts' ON CONFLICT DO NOTHING skip them from now on.
+DELETE FROM match_timeline
+WHERE EXISTS (
+  SELECT 1 FROM match_timeline b
+  WHERE b.mid = match_timeline.mid
+    AND b."frameIndex" = match_timeline."frameIndex"
+    AND b.tid < match_timeline.tid
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_match_timeline_mid_frame
+  ON match_timeline(mid, "frameIndex");
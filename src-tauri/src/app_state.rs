@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use reqwest::Client;
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+use crate::{
+    error::DianaError,
+    riot::{self, RiotClient},
+    store::{self, Store},
+};
+
+/// How often the health monitor polls the proxy.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Patches ship every couple of weeks, so a long-running app refetches the version
+/// now and then instead of keeping the one it started with.
+const DDRAGON_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// What the commands share for the lifetime of the app. Registered with
+/// `tauri::Builder::manage` at startup.
+pub struct AppState {
+    pub client: RiotClient,
+    pub store: Arc<dyn Store>,
+    pub ddragon: DdragonCache,
+    pub health: Arc<ProxyHealth>,
+}
+
+impl AppState {
+    /// Builds the client and store from the environment and starts the health monitor.
+    pub async fn init(app: &AppHandle) -> Result<Self> {
+        let client = RiotClient::from_env()?;
+        let store = store::store_from_env(app, client.http()).await?;
+
+        let mut health_urls: Vec<String> =
+            client.source().health_url().into_iter().chain(store.health_url()).collect();
+        health_urls.dedup();
+        let health = Arc::new(ProxyHealth::new(client.http().clone(), health_urls));
+        health.check().await;
+        health.clone().spawn_monitor();
+
+        Ok(Self { client, store, ddragon: DdragonCache::default(), health })
+    }
+}
+
+/// Whether diana-proxy answers, kept up to date by a background task so commands
+/// only read the last result. Always available when nothing goes through the proxy.
+pub struct ProxyHealth {
+    http: Client,
+    urls: Vec<String>,
+    /// Health URLs that did not answer the last check.
+    unreachable: RwLock<HashSet<String>>,
+}
+
+impl ProxyHealth {
+    fn new(http: Client, urls: Vec<String>) -> Self {
+        Self { http, urls, unreachable: RwLock::new(HashSet::new()) }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.unreachable.read().unwrap().is_empty()
+    }
+
+    /// Fails with [`DianaError::ProxyUnavailable`] if the last check did not get through.
+    pub fn ensure_available(&self) -> Result<(), DianaError> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(DianaError::ProxyUnavailable("health check failed".to_string()))
+        }
+    }
+
+    /// Like [`ProxyHealth::ensure_available`], but only looks at the server behind
+    /// `store`. For commands that read stored data and never call Riot.
+    pub fn ensure_store_available(&self, store: &dyn Store) -> Result<(), DianaError> {
+        match store.health_url() {
+            Some(url) if self.unreachable.read().unwrap().contains(&url) => {
+                Err(DianaError::ProxyUnavailable("health check failed".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn check(&self) {
+        let mut unreachable = HashSet::new();
+        for url in &self.urls {
+            if !ping(&self.http, url).await {
+                unreachable.insert(url.clone());
+            }
+        }
+        let available = unreachable.is_empty();
+        let was_available =
+            std::mem::replace(&mut *self.unreachable.write().unwrap(), unreachable).is_empty();
+        if available != was_available {
+            eprintln!(
+                "[HEALTH] Proxy is {}",
+                if available { "back up" } else { "unreachable" }
+            );
+        }
+    }
+
+    fn spawn_monitor(self: Arc<Self>) {
+        if self.urls.is_empty() {
+            return;
+        }
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+                self.check().await;
+            }
+        });
+    }
+}
+
+async fn ping(http: &Client, url: &str) -> bool {
+    match http.get(url).timeout(Duration::from_secs(5)).send().await {
+        Ok(response) if response.status().is_success() => true,
+        Ok(response) => {
+            eprintln!("[HEALTH] {} returned status {}", url, response.status());
+            false
+        }
+        Err(e) => {
+            eprintln!("[HEALTH] Failed to reach {}: {}", url, e);
+            false
+        }
+    }
+}
+
+/// Latest DDragon version, refetched once it is older than [`DDRAGON_TTL`]. If the
+/// refetch fails the previous version keeps being used.
+#[derive(Default)]
+pub struct DdragonCache {
+    latest: Mutex<Option<(String, Instant)>>,
+}
+
+impl DdragonCache {
+    pub async fn version(&self, client: &RiotClient) -> Result<String> {
+        let mut latest = self.latest.lock().await;
+        if let Some((version, fetched_at)) = latest.as_ref() {
+            if fetched_at.elapsed() < DDRAGON_TTL {
+                return Ok(version.clone());
+            }
+        }
+
+        match riot::get_latest_ddragon_version(client).await {
+            Ok(version) => {
+                eprintln!("[DDRAGON] Latest version: {}", version);
+                *latest = Some((version.clone(), Instant::now()));
+                Ok(version)
+            }
+            Err(e) => match latest.as_ref() {
+                Some((version, _)) => {
+                    eprintln!("[DDRAGON] Refresh failed, keeping {}: {:#}", version, e);
+                    Ok(version.clone())
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
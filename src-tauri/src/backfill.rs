@@ -0,0 +1,231 @@
+use anyhow::Result;
+
+use crate::{
+    models::BackfillCheckpoint,
+    ingest::{self, IngestOptions, MatchIngestResult, MatchOutcome},
+    riot::{MatchIdFilter, MatchIdPager, RiotClient},
+    store::Store,
+};
+
+/// Matches a timeline backfill looks at when the caller does not say.
+pub const DEFAULT_TIMELINE_BACKFILL_LIMIT: i64 = 50;
+
+/// How far back a backfill goes. With neither set it walks the whole history Riot keeps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BackfillLimits {
+    /// Oldest game to fetch, in epoch seconds.
+    pub cutoff_time: Option<i64>,
+    /// How many games of history to cover, counting ones that were already stored.
+    pub max_matches: Option<i32>,
+}
+
+/// Pages back through a player's match history and stores every missing match.
+///
+/// Progress is checkpointed after each page, so calling this again after a crash or
+/// restart picks up where the last run stopped. A finished backfill is a no-op unless
+/// different limits are passed in.
+pub async fn run_backfill(
+    client: &RiotClient,
+    store: &dyn Store,
+    regional: &str,
+    puuid: &str,
+    limits: BackfillLimits,
+) -> Result<BackfillCheckpoint> {
+    let mut checkpoint = match store.get_backfill_checkpoint(puuid).await? {
+        Some(cp) if cp.cutoff_time == limits.cutoff_time && cp.max_matches == limits.max_matches => {
+            eprintln!(
+                "[BACKFILL] Resuming {} at offset {} ({} matches fetched so far)",
+                puuid, cp.next_start, cp.matches_fetched
+            );
+            cp
+        }
+        _ => BackfillCheckpoint {
+            puuid: puuid.to_string(),
+            regional: regional.to_string(),
+            anchor_time: chrono::Utc::now().timestamp(),
+            cutoff_time: limits.cutoff_time,
+            max_matches: limits.max_matches,
+            next_start: 0,
+            oldest_game_creation: None,
+            matches_fetched: 0,
+            matches_skipped: 0,
+            completed: false,
+        },
+    };
+
+    if checkpoint.completed {
+        eprintln!("[BACKFILL] Backfill for {} already complete", puuid);
+        return Ok(checkpoint);
+    }
+
+    let filter = MatchIdFilter {
+        start_time: checkpoint.cutoff_time,
+        end_time: Some(checkpoint.anchor_time),
+        ..Default::default()
+    };
+    let mut pager = MatchIdPager::new(client, &checkpoint.regional, puuid, filter)
+        .starting_at(checkpoint.next_start as u32);
+
+    loop {
+        let remaining = checkpoint.max_matches.map(|max| max - checkpoint.next_start);
+        if remaining.is_some_and(|r| r <= 0) {
+            checkpoint.completed = true;
+            break;
+        }
+
+        let Some(mut page) = pager.next_page().await? else {
+            checkpoint.completed = true;
+            break;
+        };
+        if let Some(remaining) = remaining {
+            page.truncate(remaining as usize);
+        }
+        eprintln!(
+            "[BACKFILL] {}: page at offset {} with {} IDs",
+            puuid, checkpoint.next_start, page.len()
+        );
+
+        // Timelines are left for the timeline backfill to keep history paging cheap.
+        let results = ingest::ingest_matches(
+            client,
+            store,
+            &checkpoint.regional,
+            puuid,
+            &page,
+            IngestOptions::from_env(true),
+            |_| {},
+        )
+        .await;
+
+        // Stop at the first page with a failure that may go away so the next run retries
+        // it rather than moving the checkpoint past a gap. Matches that fail the same way
+        // every time, like ones Riot does not have, are counted as skipped instead.
+        if let Some(failed) = results.iter().find(|r| r.is_retryable()) {
+            store.save_backfill_checkpoint(&checkpoint).await?;
+            if let MatchOutcome::Failed { error, .. } = &failed.outcome {
+                anyhow::bail!("Backfill stopped at match {}: {}", failed.match_id, error);
+            }
+        }
+
+        let (mut creations, already_stored) = tally_page(&mut checkpoint, &results);
+        // Matches stored by an earlier sync still count towards how far back history goes.
+        if !already_stored.is_empty() {
+            creations.extend(store.oldest_game_creation(&already_stored).await?);
+        }
+        checkpoint.oldest_game_creation =
+            checkpoint.oldest_game_creation.into_iter().chain(creations).min();
+        store.save_backfill_checkpoint(&checkpoint).await?;
+    }
+
+    store.save_backfill_checkpoint(&checkpoint).await?;
+    eprintln!(
+        "[BACKFILL] Backfill for {} complete: {} matches fetched, {} skipped",
+        puuid, checkpoint.matches_fetched, checkpoint.matches_skipped
+    );
+    Ok(checkpoint)
+}
+
+/// Moves `checkpoint` past a page without retryable failures. Returns the game creations
+/// of the newly stored matches and the IDs of the ones that were already stored.
+fn tally_page(
+    checkpoint: &mut BackfillCheckpoint,
+    results: &[MatchIngestResult],
+) -> (Vec<i64>, Vec<String>) {
+    let mut creations = Vec::new();
+    let mut already_stored = Vec::new();
+    for result in results {
+        match &result.outcome {
+            MatchOutcome::Inserted { game_creation } => {
+                checkpoint.matches_fetched += 1;
+                creations.push(*game_creation);
+            }
+            MatchOutcome::AlreadyStored => already_stored.push(result.match_id.clone()),
+            MatchOutcome::Failed { error, .. } => {
+                eprintln!("[BACKFILL] Skipping match {}: {}", result.match_id, error);
+                checkpoint.matches_skipped += 1;
+            }
+        }
+    }
+    checkpoint.next_start += results.len() as i32;
+    (creations, already_stored)
+}
+
+/// Fetches timelines for up to `limit` of the player's stored matches that have none,
+/// newest first. Matches whose timeline fails are left for the next run.
+pub async fn backfill_timelines(
+    client: &RiotClient,
+    store: &dyn Store,
+    puuid: &str,
+    limit: i64,
+) -> Result<Vec<MatchIngestResult>> {
+    let rows = store.get_matches_missing_timeline(puuid, limit).await?;
+    if rows.is_empty() {
+        eprintln!("[BACKFILL] No matches without timeline for {}", puuid);
+        return Ok(Vec::new());
+    }
+
+    eprintln!("[BACKFILL] Fetching {} missing timelines for {}", rows.len(), puuid);
+    let results =
+        ingest::ingest_timelines(client, store, &rows, ingest::concurrency_from_env(), |_| {}).await;
+    eprintln!(
+        "[BACKFILL] Timeline backfill for {} done: {} of {} failed",
+        puuid,
+        results.iter().filter(|r| r.is_failed()).count(),
+        results.len()
+    );
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DianaError;
+
+    fn checkpoint() -> BackfillCheckpoint {
+        BackfillCheckpoint {
+            puuid: "me".to_string(),
+            regional: "europe".to_string(),
+            anchor_time: 1_700_000_000,
+            cutoff_time: None,
+            max_matches: None,
+            next_start: 100,
+            oldest_game_creation: None,
+            matches_fetched: 0,
+            matches_skipped: 0,
+            completed: false,
+        }
+    }
+
+    fn result(match_id: &str, outcome: MatchOutcome) -> MatchIngestResult {
+        MatchIngestResult { match_id: match_id.to_string(), outcome }
+    }
+
+    fn failed(error: DianaError) -> MatchOutcome {
+        MatchOutcome::Failed { error: error.to_string(), retryable: error.is_transient() }
+    }
+
+    #[test]
+    fn a_match_riot_does_not_have_is_skipped() {
+        let results = [
+            result("EUW1_3", MatchOutcome::Inserted { game_creation: 3 }),
+            result("EUW1_2", failed(DianaError::MatchNotFound("EUW1_2".to_string()))),
+            result("EUW1_1", MatchOutcome::AlreadyStored),
+        ];
+        assert!(!results.iter().any(|r| r.is_retryable()));
+
+        let mut checkpoint = checkpoint();
+        let (creations, already_stored) = tally_page(&mut checkpoint, &results);
+        assert_eq!(checkpoint.next_start, 103);
+        assert_eq!((checkpoint.matches_fetched, checkpoint.matches_skipped), (1, 1));
+        assert_eq!(creations, [3]);
+        assert_eq!(already_stored, ["EUW1_1"]);
+    }
+
+    #[test]
+    fn rate_limits_and_riot_outages_are_retried() {
+        assert!(DianaError::RateLimited { retry_after: Some(10) }.is_transient());
+        assert!(DianaError::Riot { status: 503, message: String::new() }.is_transient());
+        assert!(!DianaError::Riot { status: 400, message: String::new() }.is_transient());
+        assert!(result("EUW1_1", failed(DianaError::RateLimited { retry_after: None })).is_retryable());
+    }
+}
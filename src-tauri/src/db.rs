@@ -1,55 +1,94 @@
 use anyhow::{anyhow, Result};
+use chrono::{Datelike, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+use sqlx::migrate::Migrator;
 use sqlx::{Pool, Postgres, Transaction};
 use serde_json::json;
 use serde_json::Value;
 
-use crate::models::{DbMatchRow, DbSummoner, RankStep};
+use crate::error::DianaError;
+use crate::match_summary::{participant_detail, summarize_participant, MatchSummary};
+use crate::queues::{QueueGroup, RANKED_SOLO};
+use crate::rank::Rank;
+use crate::roles::{champions_without_role, role_stats, ChampionRole, RoleGame, SUMMONERS_RIFT};
+use crate::models::{
+    BackfillCheckpoint, DbMatchRow, DbSummoner, DbTeammate, GameResult, MatchDetails,
+    MatchupStats, RankSnapshot, RankStep, RoleStats, Streaks, TimelineFrame,
+};
+use crate::store::{NewMatchDetails, NewSummoner};
 
 pub type PgPool = Pool<Postgres>;
 pub type PgTx<'a> = Transaction<'a, Postgres>;
 
-pub async fn init_pool() -> Result<PgPool> {
+/// Postgres schema, from `migrations/`. diana-proxy uses the same schema.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Connections are opened on first use, so the app starts while the database is down.
+pub fn init_pool() -> Result<PgPool> {
     let url = std::env::var("DATABASE_URL")
         .map_err(|_| anyhow!("DATABASE_URL not set"))?;
     let pool = sqlx::postgres::PgPoolOptions::new()
         .max_connections(5)
-        .connect(&url)
-        .await?;
+        .connect_lazy(&url)?;
     Ok(pool)
 }
 
+/// Applies the migrations the database has not seen yet.
+pub async fn migrate(pool: &PgPool) -> Result<()> {
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
+
+/// Newest migration applied to the database, `None` if it was never migrated.
+pub async fn schema_version(pool: &PgPool) -> Result<Option<i64>> {
+    let tracked = sqlx::query_scalar::<Postgres, bool>(
+        r#"SELECT to_regclass('public._sqlx_migrations') IS NOT NULL"#
+    )
+    .fetch_one(pool)
+    .await?;
+    if !tracked {
+        return Ok(None);
+    }
+
+    let version = sqlx::query_scalar::<Postgres, Option<i64>>(
+        r#"SELECT MAX(version) FROM public._sqlx_migrations WHERE success"#
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(version)
+}
+
 pub async fn upsert_summoner(
     pool: &PgPool,
-    puuid: &str,
-    game_name: &str,
-    tag_line: &str,
-    region: &str,
-    tier: Option<&str>,
-    rank: Option<&str>,
-    lp: Option<i32>,
+    summoner: NewSummoner<'_>,
 ) -> Result<()> {
     sqlx::query(
         r#"
         INSERT INTO public.summoners
-        (puuid, "gameName", "tagLine", region, tier, rank, lp, "lastUpdated")
-        VALUES ($1, $2, $3, $4, COALESCE($5, 'UNRANKED'), $6, COALESCE($7, 0), now())
+        (puuid, "gameName", "tagLine", region, "matchRegionPrefix", "regionGroup",
+         tier, rank, lp, "lastUpdated")
+        VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 'UNRANKED'), $8, COALESCE($9, 0), now())
         ON CONFLICT (puuid) DO UPDATE
         SET "gameName" = EXCLUDED."gameName",
             "tagLine" = EXCLUDED."tagLine",
             region = EXCLUDED.region,
+            "matchRegionPrefix" = EXCLUDED."matchRegionPrefix",
+            "regionGroup" = EXCLUDED."regionGroup",
             tier = EXCLUDED.tier,
             rank = EXCLUDED.rank,
             lp = EXCLUDED.lp,
             "lastUpdated" = now()
         "#
     )
-    .bind(puuid)
-    .bind(game_name)
-    .bind(tag_line)
-    .bind(region)
-    .bind(tier)
-    .bind(rank)
-    .bind(lp)
+    .bind(summoner.puuid)
+    .bind(summoner.game_name)
+    .bind(summoner.tag_line)
+    .bind(summoner.region)
+    .bind(summoner.match_region_prefix)
+    .bind(summoner.region_group)
+    .bind(summoner.tier)
+    .bind(summoner.rank)
+    .bind(summoner.lp)
     .execute(pool)
     .await?;
     Ok(())
@@ -81,7 +120,34 @@ pub async fn match_exists(pool: &PgPool, match_id: &str) -> Result<bool> {
     Ok(rec)
 }
 
-pub async fn latest_match_for_puuid(pool: &PgPool, puuid: &str) -> Result<Option<String>> {
+/// Which of `match_ids` are already stored.
+pub async fn matches_exist(pool: &PgPool, match_ids: &[String]) -> Result<HashSet<String>> {
+    let rows = sqlx::query_scalar::<Postgres, String>(
+        r#"
+        SELECT "matchId" FROM public.match_details WHERE "matchId" = ANY($1)
+        "#
+    )
+    .bind(match_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Earliest `gameCreation` among the stored ones of `match_ids`.
+pub async fn oldest_game_creation(pool: &PgPool, match_ids: &[String]) -> Result<Option<i64>> {
+    let oldest = sqlx::query_scalar::<Postgres, Option<i64>>(
+        r#"
+        SELECT MIN("gameCreation") FROM public.match_details WHERE "matchId" = ANY($1)
+        "#
+    )
+    .bind(match_ids)
+    .fetch_one(pool)
+    .await?;
+    Ok(oldest)
+}
+
+/// Most recent stored match of `puuid`, the anchor for incremental syncs.
+pub async fn latest_match_for_puuid(pool: &PgPool, puuid: &str) -> Result<Option<DbMatchRow>> {
     let row = sqlx::query_as::<_, DbMatchRow>(
         r#"
         SELECT mid, "matchId", "entryPlayerPuuid", "gameCreation"
@@ -94,7 +160,7 @@ pub async fn latest_match_for_puuid(pool: &PgPool, puuid: &str) -> Result<Option
     .bind(puuid)
     .fetch_optional(pool)
     .await?;
-    Ok(row.map(|r| r.matchId))
+    Ok(row)
 }
 
 pub async fn get_recent_matches(pool: &PgPool, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
@@ -114,21 +180,54 @@ pub async fn get_recent_matches(pool: &PgPool, puuid: &str, limit: i64) -> Resul
     Ok(rows)
 }
 
+/// Stored matches of `puuid` that have no timeline frames yet, newest first.
+pub async fn get_matches_missing_timeline(
+    pool: &PgPool,
+    puuid: &str,
+    limit: i64,
+) -> Result<Vec<DbMatchRow>> {
+    let rows = sqlx::query_as::<_, DbMatchRow>(
+        r#"
+        SELECT md.mid, md."matchId", md."entryPlayerPuuid", md."gameCreation"
+        FROM public.match_details md
+        WHERE md."entryPlayerPuuid" = $1
+          AND NOT EXISTS (
+            SELECT 1 FROM public.match_timeline mt WHERE mt.mid = md.mid
+          )
+          AND NOT EXISTS (
+            SELECT 1 FROM public.timeline_unavailable tu WHERE tu.mid = md.mid
+          )
+        ORDER BY md."gameCreation" DESC
+        LIMIT $2
+        "#
+    )
+    .bind(puuid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn mark_timeline_unavailable(pool: &PgPool, mid: i64, reason: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.timeline_unavailable (mid, reason)
+        VALUES ($1, $2)
+        ON CONFLICT (mid) DO UPDATE SET reason = EXCLUDED.reason
+        "#
+    )
+    .bind(mid)
+    .bind(reason)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Returns the row's `mid`. A match that is already stored keeps its row, e.g. when
+/// the background sync and [`crate::sync::ensure_match_stored`] race on it.
 pub async fn insert_match_details_tx(
     tx: &mut PgTx<'_>,
-    match_id: &str,
-    entry_puuid: &str,
-    game_version: Option<&str>,
-    game_creation: Option<i64>,
-    game_start: Option<i64>,
-    game_end: Option<i64>,
-    game_duration: Option<i32>,
-    game_mode: Option<&str>,
-    game_type: Option<&str>,
-    queue_type: Option<i32>,
-    map_name: Option<i32>,
-    participants: &Value,
-    teams: &Value,
+    row: NewMatchDetails<'_>,
 ) -> Result<i64> {
     let rec = sqlx::query_scalar::<Postgres, i64>(
         r#"
@@ -137,103 +236,194 @@ pub async fn insert_match_details_tx(
          "gameStartTime", "gameEndTime", "gameDuration", "gameMode",
          "gameType", "queueType", "mapName", participants, teams)
         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
-        ON CONFLICT DO NOTHING
+        ON CONFLICT ("matchId") DO UPDATE SET "matchId" = EXCLUDED."matchId"
         RETURNING mid
         "#
     )
-    .bind(match_id)
-    .bind(entry_puuid)
-    .bind(game_version)
-    .bind(game_creation)
-    .bind(game_start)
-    .bind(game_end)
-    .bind(game_duration)
-    .bind(game_mode)
-    .bind(game_type)
-    .bind(queue_type)
-    .bind(map_name)
-    .bind(participants)
-    .bind(teams)
+    .bind(row.match_id)
+    .bind(row.entry_puuid)
+    .bind(row.game_version)
+    .bind(row.game_creation)
+    .bind(row.game_start)
+    .bind(row.game_end)
+    .bind(row.game_duration)
+    .bind(row.game_mode)
+    .bind(row.game_type)
+    .bind(row.queue_type)
+    .bind(row.map_name)
+    .bind(row.participants)
+    .bind(row.teams)
     .fetch_one(tx.as_mut())
     .await?;
+
+    insert_match_participants_tx(tx, rec).await?;
     Ok(rec)
 }
 
-pub async fn insert_timeline_frame_tx(
+/// Fills `match_participants` from the participants JSON of a just inserted match.
+async fn insert_match_participants_tx(tx: &mut PgTx<'_>, mid: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.match_participants
+        (mid, puuid, team_id, champion_name, team_position, kills, deaths, assists, cs,
+         gold_earned, damage_to_champions, vision_score, win)
+        SELECT
+          md.mid,
+          p->>'puuid',
+          (p->>'teamId')::int,
+          p->>'championName',
+          NULLIF(p->>'teamPosition', ''),
+          COALESCE((p->>'kills')::int, 0),
+          COALESCE((p->>'deaths')::int, 0),
+          COALESCE((p->>'assists')::int, 0),
+          COALESCE((p->>'totalMinionsKilled')::int, 0)
+            + COALESCE((p->>'neutralMinionsKilled')::int, 0),
+          COALESCE((p->>'goldEarned')::int, 0),
+          COALESCE((p->>'totalDamageDealtToChampions')::int, 0),
+          COALESCE((p->>'visionScore')::int, 0),
+          COALESCE((p->>'win')::boolean, false)
+        FROM public.match_details md
+        JOIN LATERAL jsonb_array_elements(md.participants) p ON true
+        WHERE md.mid = $1 AND p->>'puuid' IS NOT NULL
+        ON CONFLICT (mid, puuid) DO NOTHING
+        "#
+    )
+    .bind(mid)
+    .execute(tx.as_mut())
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_timeline_frames_tx(
     tx: &mut PgTx<'_>,
     mid: i64,
     entry_participant_id: &str,
-    frame_index: Option<i32>,
-    timestamp: Option<i64>,
-    participant_frames: &Value,
-    events: &Value,
-) -> Result<i64> {
-    let rec = sqlx::query_scalar::<Postgres, i64>(
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    let mut frame_indexes = Vec::with_capacity(frames.len());
+    let mut timestamps = Vec::with_capacity(frames.len());
+    let mut participant_frames = Vec::with_capacity(frames.len());
+    let mut events = Vec::with_capacity(frames.len());
+    for frame in frames {
+        frame_indexes.push(frame.frame_index);
+        timestamps.push(frame.timestamp);
+        participant_frames.push(frame.participant_frames);
+        events.push(frame.events);
+    }
+
+    sqlx::query(
         r#"
         INSERT INTO public.match_timeline
         (mid, "entryParticipantId", "frameIndex", "timestamp",
          "participantFrames", events)
-        VALUES ($1,$2,$3,$4,$5,$6)
+        SELECT $1, $2, f."frameIndex", f."timestamp", f."participantFrames", f.events
+        FROM unnest($3::int[], $4::bigint[], $5::jsonb[], $6::jsonb[])
+          AS f("frameIndex", "timestamp", "participantFrames", events)
         ON CONFLICT DO NOTHING
-        RETURNING tid
         "#
     )
     .bind(mid)
     .bind(entry_participant_id)
-    .bind(frame_index)
-    .bind(timestamp)
+    .bind(frame_indexes)
+    .bind(timestamps)
     .bind(participant_frames)
     .bind(events)
-    .fetch_one(tx.as_mut())
+    .execute(tx.as_mut())
     .await?;
-    Ok(rec)
+    Ok(())
 }
 
-pub async fn timeline_frame_exists_tx(
-    tx: &mut PgTx<'_>,
+/// Stores all frames of an already stored match.
+pub async fn insert_timeline_frames(
+    pool: &PgPool,
     mid: i64,
-    frame_index: i32,
-) -> Result<bool> {
-    let rec = sqlx::query_scalar::<Postgres, bool>(
-        r#"
-        SELECT EXISTS(
-            SELECT 1
-            FROM public.match_timeline
-            WHERE mid = $1 AND "frameIndex" = $2
-        )
-        "#
-    )
-    .bind(mid)
-    .bind(frame_index)
-    .fetch_one(tx.as_mut())
-    .await?;
-    Ok(rec)
+    entry_participant_id: &str,
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    insert_timeline_frames_tx(&mut tx, mid, entry_participant_id, frames).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+pub(crate) struct SummaryRow {
+    games: Option<i32>,
+    wins: Option<i32>,
+    losses: Option<i32>,
+    tk: Option<i32>,
+    td: Option<i32>,
+    ta: Option<i32>,
+    top_champs: Option<serde_json::Value>,
+}
+
+/// Games, wins, losses, KDA, winrate and top champions (with icons) of a summary row.
+pub(crate) fn summary_totals(row: &SummaryRow) -> (i32, i32, i32, f32, f32, serde_json::Value) {
+    let games = row.games.unwrap_or(0);
+    let wins = row.wins.unwrap_or(0);
+    let losses = row.losses.unwrap_or(0);
+    let tk = row.tk.unwrap_or(0) as f32;
+    let td = row.td.unwrap_or(0) as f32;
+    let ta = row.ta.unwrap_or(0) as f32;
+    let kda = if td > 0.0 { (tk + ta) / td } else { tk + ta };
+    let winrate = if games > 0 { (wins as f32 / games as f32) * 100.0 } else { 0.0 };
+
+    let ddragon_version = "14.18.1";
+    let mut champs_with_icons = Vec::new();
+    if let Some(arr) = row.top_champs.as_ref().and_then(|v| v.as_array()) {
+        for c in arr {
+            if let (Some(name), Some(games), Some(wins), Some(winrate), Some(kda)) = (
+                c.get("champion_name").and_then(|v| v.as_str()),
+                c.get("games").and_then(|v| v.as_i64()),
+                c.get("wins").and_then(|v| v.as_i64()),
+                c.get("winrate").and_then(|v| v.as_i64()),
+                c.get("kda").and_then(|v| v.as_f64()),
+            ) {
+                champs_with_icons.push(json!({
+                    "champion_name": name,
+                    "games": games,
+                    "wins": wins,
+                    "winrate": winrate,
+                    "kda": kda,
+                    "icon_url": format!(
+                        "https://ddragon.leagueoflegends.com/cdn/{}/img/champion/{}.png",
+                        ddragon_version, name
+                    )
+                }));
+            }
+        }
+    }
+    (games, wins, losses, kda, winrate, serde_json::Value::Array(champs_with_icons))
+}
+
+/// Start of the current ranked season, in epoch ms. Seasons start in early January, so
+/// this is the start of the year (UTC).
+pub(crate) fn season_start_ms() -> i64 {
+    let year = Utc::now().year();
+    Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0)
+        .single()
+        .map_or(0, |start| start.timestamp_millis())
 }
 
 pub async fn compute_player_summary(
     pool: &PgPool,
     puuid: &str,
-) -> Result<(i32, i32, i32, f32, f32, i32, serde_json::Value)> {
-    let row = sqlx::query!(
+    queue: QueueGroup,
+) -> Result<(i32, i32, i32, f32, f32, Streaks, serde_json::Value)> {
+    let row = sqlx::query_as::<_, SummaryRow>(
         r#"
-        WITH me AS (
+        WITH ranked AS (
           SELECT
-            md."matchId" AS match_id,
-            md."gameCreation" AS game_creation,
-            (p->>'win')::boolean AS win,
-            (p->>'kills')::int  AS k,
-            (p->>'deaths')::int AS d,
-            (p->>'assists')::int AS a,
-            (p->>'championName')::text AS champion,
-            md."queueType" AS queue_id,
-            md."gameDuration" AS duration_s
-          FROM public.match_details md
-          JOIN LATERAL jsonb_array_elements(md.participants) p ON true
-          WHERE p->>'puuid' = $1
-        ),
-        ranked AS (
-          SELECT * FROM me
-          WHERE queue_id = 420 AND COALESCE(duration_s,0) >= 300
+            mp.win,
+            mp.kills AS k,
+            mp.deaths AS d,
+            mp.assists AS a,
+            mp.champion_name AS champion
+          FROM public.match_participants mp
+          JOIN public.match_details md ON md.mid = mp.mid
+          WHERE mp.puuid = $1
+            AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+            AND COALESCE(md."gameDuration",0) >= 300
         ),
         sums AS (
           SELECT
@@ -283,67 +473,57 @@ pub async fn compute_player_summary(
                 FROM champs c
             ) AS top_champs
             FROM sums;
-        "#,
-        puuid
+        "#
     )
+    .bind(puuid)
+    .bind(queue.queue_ids())
     .fetch_one(pool)
     .await?;
 
-    let games = row.games.unwrap_or(0);
-    let wins = row.wins.unwrap_or(0);
-    let losses = row.losses.unwrap_or(0);
-    let tk = row.tk.unwrap_or(0) as f32;
-    let td = row.td.unwrap_or(0) as f32;
-    let ta = row.ta.unwrap_or(0) as f32;
-    let kda = if td > 0.0 { (tk + ta) / td } else { tk + ta };
-    let winrate = if games > 0 { (wins as f32 / games as f32) * 100.0 } else { 0.0 };
+    let (games, wins, losses, kda, winrate, top_champs) = summary_totals(&row);
 
-    let ddragon_version = "14.18.1";
-    let mut champs_with_icons = Vec::new();
-    if let Some(arr) = row.top_champs.as_ref().and_then(|v| v.as_array()) {
-        for c in arr {
-            if let (Some(name), Some(games), Some(wins), Some(winrate), Some(kda)) = (
-                c.get("champion_name").and_then(|v| v.as_str()),
-                c.get("games").and_then(|v| v.as_i64()),
-                c.get("wins").and_then(|v| v.as_i64()),
-                c.get("winrate").and_then(|v| v.as_i64()),
-                c.get("kda").and_then(|v| v.as_f64()),
-            ) {
-                champs_with_icons.push(json!({
-                    "champion_name": name,
-                    "games": games,
-                    "wins": wins,
-                    "winrate": winrate,
-                    "kda": kda,
-                    "icon_url": format!(
-                        "https://ddragon.leagueoflegends.com/cdn/{}/img/champion/{}.png",
-                        ddragon_version, name
-                    )
-                }));
-            }
-        }
-    }
-    let top_champs = serde_json::Value::Array(champs_with_icons);
+    let results = sqlx::query_as::<_, GameResult>(
+        r#"
+        SELECT mp.win,
+               md."gameCreation" AS game_creation,
+               COALESCE(md."gameDuration",0) AS game_duration
+        FROM public.match_participants mp
+        JOIN public.match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+          AND md."gameCreation" IS NOT NULL
+        ORDER BY md."gameCreation"
+        "#
+    )
+    .bind(puuid)
+    .bind(queue.queue_ids())
+    .fetch_all(pool)
+    .await?;
+    let streaks = Streaks::from_results(&results, season_start_ms());
 
-    sqlx::query!(
+    sqlx::query(
         r#"
         INSERT INTO public.player_summary
-        (puuid, queue_id, games, wins, losses, avg_kda, winrate, streak, top_champs, computed_at)
-        VALUES ($1,$2,$3,$4,$5,$6::float8,$7::float8,$8,$9, now())
-        ON CONFLICT (puuid) DO UPDATE
-        SET queue_id=$2, games=$3, wins=$4, losses=$5,
-            avg_kda=$6::float8, winrate=$7::float8, streak=$8, top_champs=$9, computed_at=now()
-        "#,
-        puuid,
-        420,
-        games,
-        wins,
-        losses,
-        (kda as f64),
-        (winrate as f64),
-        0,
-        top_champs
+        (puuid, queue, games, wins, losses, avg_kda, winrate, streak,
+         longest_win_streak, longest_loss_streak, top_champs, computed_at)
+        VALUES ($1,$2,$3,$4,$5,$6::float8,$7::float8,$8,$9,$10,$11, now())
+        ON CONFLICT (puuid, queue) DO UPDATE
+        SET games=$3, wins=$4, losses=$5,
+            avg_kda=$6::float8, winrate=$7::float8, streak=$8, longest_win_streak=$9,
+            longest_loss_streak=$10, top_champs=$11, computed_at=now()
+        "#
     )
+    .bind(puuid)
+    .bind(queue.as_str())
+    .bind(games)
+    .bind(wins)
+    .bind(losses)
+    .bind(kda as f64)
+    .bind(winrate as f64)
+    .bind(streaks.current)
+    .bind(streaks.longest_win)
+    .bind(streaks.longest_loss)
+    .bind(&top_champs)
     .execute(pool)
     .await?;
 
@@ -353,7 +533,7 @@ pub async fn compute_player_summary(
         losses,
         (kda * 100.0).round() / 100.0,
         (winrate * 100.0).round() / 100.0,
-        0,
+        streaks,
         top_champs,
     ))
 }
@@ -361,18 +541,20 @@ pub async fn compute_player_summary(
 pub async fn get_daily_activity(
     pool: &PgPool,
     puuid: &str,
+    queue: QueueGroup,
 ) -> Result<Vec<crate::models::DailyActivityEntry>> {
-    let rows = sqlx::query!(
+    let rows = sqlx::query_as::<_, (Option<String>, Option<i32>)>(
         r#"
         WITH daily_counts AS (
             SELECT 
-                DATE(to_timestamp("gameCreation" / 1000)) as game_date,
+                DATE(to_timestamp(md."gameCreation" / 1000)) as game_date,
                 COUNT(*) as games
-            FROM public.match_details
-            WHERE "entryPlayerPuuid" = $1
-                AND "gameCreation" >= extract(epoch from (now() - interval '30 days')) * 1000
-                AND "queueType" = 420
-            GROUP BY DATE(to_timestamp("gameCreation" / 1000))
+            FROM public.match_participants mp
+            JOIN public.match_details md ON md.mid = mp.mid
+            WHERE mp.puuid = $1
+                AND md."gameCreation" >= extract(epoch from (now() - interval '30 days')) * 1000
+                AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+            GROUP BY DATE(to_timestamp(md."gameCreation" / 1000))
         ),
         date_series AS (
             SELECT generate_series(
@@ -387,23 +569,45 @@ pub async fn get_daily_activity(
         FROM date_series ds
         LEFT JOIN daily_counts dc ON ds.date = dc.game_date
         ORDER BY ds.date
-        "#,
-        puuid
+        "#
     )
+    .bind(puuid)
+    .bind(queue.queue_ids())
     .fetch_all(pool)
     .await?;
 
     let mut entries = Vec::new();
-    for row in rows {
+    for (date, games) in rows {
         entries.push(crate::models::DailyActivityEntry {
-            date: row.date.unwrap_or_default(),
-            games: row.games.unwrap_or(0),
+            date: date.unwrap_or_default(),
+            games: games.unwrap_or(0),
         });
     }
 
     Ok(entries)
 }
 
+pub async fn save_rank_snapshot(
+    pool: &PgPool,
+    puuid: &str,
+    snapshot: &RankSnapshot,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.rank_snapshots (puuid, tier, division, lp, taken_at)
+        VALUES ($1,$2,$3,$4,$5)
+        "#
+    )
+    .bind(puuid)
+    .bind(&snapshot.tier)
+    .bind(&snapshot.division)
+    .bind(snapshot.lp)
+    .bind(snapshot.taken_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 pub async fn compute_rank_progress_and_cache(
     pool: &PgPool,
     puuid: &str,
@@ -411,67 +615,603 @@ pub async fn compute_rank_progress_and_cache(
     current_division: &str,
     current_lp: i32,
 ) -> Result<Vec<RankStep>> {
-    let rows = sqlx::query!(
+    let games = sqlx::query_as::<_, RankedGame>(
         r#"
-        SELECT m."matchId", m."gameCreation", (p->>'win')::bool as win
-        FROM public.match_details m,
-        LATERAL jsonb_array_elements(m.participants) as p
-        WHERE p->>'puuid' = $1
-          AND m."queueType" = 420
+        SELECT m."matchId" AS match_id,
+               m."gameCreation" AS game_creation,
+               m."gameCreation" + COALESCE(m."gameDuration", 0)::bigint * 1000 AS game_end,
+               mp.win
+        FROM public.match_participants mp
+        JOIN public.match_details m ON m.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND m."queueType" = $2
         ORDER BY m."gameCreation" DESC
-        LIMIT 10
-        "#,
-        puuid
+        LIMIT $3
+        "#
     )
+    .bind(puuid)
+    .bind(RANKED_SOLO)
+    .bind(RANK_PROGRESS_GAMES + 1)
     .fetch_all(pool)
     .await?;
 
-    let mut steps: Vec<RankStep> = Vec::new();
-    let mut lp = current_lp;
-
-    for (i, row) in rows.into_iter().rev().enumerate() {
-        let delta = if row.win.unwrap_or(false) { 15 } else { -15 };
-        let before = lp - delta;
-
-        let step = RankStep {
-            label_index: (i + 1) as i32,
-            lp_before: before,
-            lp_after: lp,
-            lp_delta: delta,
-            result: if row.win.unwrap_or(false) { "Win".to_string() } else { "Loss".to_string() },
-            tier_before: current_tier.to_string(),
-            division_before: current_division.to_string(),
-            tier_after: current_tier.to_string(),
-            division_after: current_division.to_string(),
-            exact: false,
-            match_id: row.matchId,
-        };
+    // From the last snapshot before the oldest game on.
+    let oldest = games.last().and_then(|g| g.game_creation).unwrap_or(0);
+    let snapshots = sqlx::query_as::<_, RankSnapshot>(
+        r#"
+        SELECT tier, division, lp, taken_at
+        FROM public.rank_snapshots
+        WHERE puuid = $1
+          AND taken_at >= COALESCE(
+            (SELECT MAX(taken_at) FROM public.rank_snapshots WHERE puuid = $1 AND taken_at < $2),
+            $2)
+        ORDER BY taken_at
+        "#
+    )
+    .bind(puuid)
+    .bind(oldest)
+    .fetch_all(pool)
+    .await?;
 
-        sqlx::query!(
+    let steps = rank_steps(&games, &snapshots, current_tier, current_division, current_lp);
+    for (step, game_creation) in &steps {
+        sqlx::query(
             r#"
             INSERT INTO public.match_rank_progress
             (puuid, match_id, game_creation, lp_before, lp_after, lp_delta,
              tier_before, division_before, tier_after, division_after, exact)
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,false)
-            ON CONFLICT (puuid, match_id) DO NOTHING
-            "#,
-            puuid,
-            step.match_id,
-            row.gameCreation,
-            step.lp_before,
-            step.lp_after,
-            step.lp_delta,
-            step.tier_before,
-            step.division_before,
-            step.tier_after,
-            step.division_after
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+            ON CONFLICT (puuid, match_id) DO UPDATE
+            SET lp_before = EXCLUDED.lp_before,
+                lp_after = EXCLUDED.lp_after,
+                lp_delta = EXCLUDED.lp_delta,
+                tier_before = EXCLUDED.tier_before,
+                division_before = EXCLUDED.division_before,
+                tier_after = EXCLUDED.tier_after,
+                division_after = EXCLUDED.division_after,
+                exact = EXCLUDED.exact,
+                computed_at = now()
+            WHERE NOT public.match_rank_progress.exact
+            "#
         )
+        .bind(puuid)
+        .bind(&step.match_id)
+        .bind(game_creation)
+        .bind(step.lp_before)
+        .bind(step.lp_after)
+        .bind(step.lp_delta)
+        .bind(&step.tier_before)
+        .bind(&step.division_before)
+        .bind(&step.tier_after)
+        .bind(&step.division_after)
+        .bind(step.exact)
         .execute(pool)
         .await?;
+    }
+
+    Ok(steps.into_iter().map(|(step, _)| step).collect())
+}
+
+pub async fn get_role_stats(pool: &PgPool, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>> {
+    let games = sqlx::query_as::<_, RoleGame>(
+        r#"
+        SELECT mp.team_position, mp.champion_name,
+               (SELECT string_agg(o.team_position, ',')
+                FROM public.match_participants o
+                WHERE o.mid = mp.mid AND o.team_id = mp.team_id AND o.puuid <> mp.puuid
+               ) AS teammate_positions,
+               mp.kills, mp.deaths, mp.assists, mp.cs, mp.gold_earned, mp.vision_score, mp.win,
+               md."gameDuration" AS game_duration,
+               (SELECT SUM(o.kills)
+                FROM public.match_participants o
+                WHERE o.mid = mp.mid AND o.team_id = mp.team_id
+               ) AS team_kills
+        FROM public.match_participants mp
+        JOIN public.match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND md."mapName" = $2
+          AND COALESCE(md."gameDuration",0) >= 300
+          AND ($3::int[] IS NULL OR md."queueType" = ANY($3))
+        "#
+    )
+    .bind(puuid)
+    .bind(SUMMONERS_RIFT)
+    .bind(queue.queue_ids())
+    .fetch_all(pool)
+    .await?;
+
+    let champions = champions_without_role(&games);
+    let champion_roles = if champions.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as::<_, ChampionRole>(
+            r#"
+            SELECT champion_name, team_position, COUNT(*) AS games
+            FROM public.match_participants
+            WHERE champion_name = ANY($1)
+              AND team_position IS NOT NULL
+            GROUP BY champion_name, team_position
+            "#
+        )
+        .bind(&champions)
+        .fetch_all(pool)
+        .await?
+    };
+
+    Ok(role_stats(&games, &champion_roles))
+}
+
+/// Matchups seen fewer times than this are left out unless the caller asks otherwise.
+/// Like any `min_games`, the count itself is kept.
+pub const DEFAULT_MATCHUP_MIN_GAMES: i32 = 3;
+
+pub async fn get_matchup_stats(
+    pool: &PgPool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<MatchupStats>> {
+    let rows = sqlx::query_as::<_, MatchupStats>(
+        r#"
+        SELECT me.champion_name,
+               opp.champion_name AS opponent_champion_name,
+               me.team_position AS role,
+               COUNT(*)::int AS games,
+               COUNT(*) FILTER (WHERE me.win)::int AS wins,
+               (100.0 * COUNT(*) FILTER (WHERE me.win) / COUNT(*))::real AS winrate,
+               AVG((me.kills + me.assists)::real / GREATEST(me.deaths, 1)
+                   - (opp.kills + opp.assists)::real / GREATEST(opp.deaths, 1))::real AS avg_kda_diff,
+               AVG(me.gold_earned - opp.gold_earned)::real AS avg_gold_diff
+        FROM public.match_participants me
+        JOIN public.match_details md ON md.mid = me.mid
+        JOIN public.match_participants opp
+          ON opp.mid = me.mid
+         AND opp.team_id <> me.team_id
+         AND opp.team_position = me.team_position
+        WHERE me.puuid = $1
+          AND me.champion_name IS NOT NULL
+          AND opp.champion_name IS NOT NULL
+          AND COALESCE(md."gameDuration",0) >= 300
+          AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+        GROUP BY me.champion_name, opp.champion_name, me.team_position
+        HAVING COUNT(*) >= $3
+        ORDER BY me.champion_name, games DESC, opp.champion_name
+        "#
+    )
+    .bind(puuid)
+    .bind(queue.queue_ids())
+    .bind(min_games)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Players seen together fewer times than this are left out unless the caller asks
+/// otherwise. Like any `min_games`, the count itself is kept.
+pub const DEFAULT_TEAMMATE_MIN_GAMES: i32 = 3;
+
+pub async fn get_teammates(
+    pool: &PgPool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<DbTeammate>> {
+    let rows = sqlx::query_as::<_, DbTeammate>(
+        r#"
+        WITH games AS (
+            SELECT mp.mid, mp.team_id, mp.win, md."gameCreation" AS game_creation
+            FROM public.match_participants mp
+            JOIN public.match_details md ON md.mid = mp.mid
+            WHERE mp.puuid = $1
+              AND COALESCE(md."gameDuration",0) >= 300
+              AND ($2::int[] IS NULL OR md."queueType" = ANY($2))
+        ),
+        totals AS (
+            SELECT COUNT(*)::int AS games, COUNT(*) FILTER (WHERE win)::int AS wins
+            FROM games
+        ),
+        mates AS (
+            SELECT mate.puuid,
+                   COUNT(*)::int AS games,
+                   COUNT(*) FILTER (WHERE g.win)::int AS wins,
+                   (ARRAY_AGG(g.mid ORDER BY g.game_creation DESC NULLS LAST))[1] AS last_mid
+            FROM games g
+            JOIN public.match_participants mate
+              ON mate.mid = g.mid
+             AND mate.team_id = g.team_id
+             AND mate.puuid <> $1
+            GROUP BY mate.puuid
+            HAVING COUNT(*) >= $3
+        )
+        SELECT m.puuid,
+               m.games,
+               m.wins,
+               totals.games AS total_games,
+               totals.wins AS total_wins,
+               (
+                   SELECT NULLIF(p->>'riotIdGameName', '') || '#' || NULLIF(p->>'riotIdTagline', '')
+                   FROM public.match_details md
+                   CROSS JOIN LATERAL jsonb_array_elements(md.participants) p
+                   WHERE md.mid = m.last_mid AND p->>'puuid' = m.puuid
+               ) AS riot_id
+        FROM mates m
+        CROSS JOIN totals
+        ORDER BY m.games DESC, m.wins DESC, m.puuid
+        "#
+    )
+    .bind(puuid)
+    .bind(queue.queue_ids())
+    .bind(min_games)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Ranked solo games shown in the LP history.
+pub(crate) const RANK_PROGRESS_GAMES: i64 = 10;
+
+/// Riot applies LP a little after a game ends, a snapshot taken sooner than this may
+/// not include the game yet and is not used for it.
+const LP_UPDATE_DELAY_MS: i64 = 60_000;
+
+/// A ranked solo game of the player, newest first. Read one past
+/// [`RANK_PROGRESS_GAMES`] so the oldest shown game knows when the game before it ended.
+#[derive(sqlx::FromRow, serde::Deserialize, Debug)]
+pub(crate) struct RankedGame {
+    match_id: String,
+    pub(crate) game_creation: Option<i64>,
+    /// Epoch ms, from `gameCreation` and `gameDuration`.
+    game_end: Option<i64>,
+    win: Option<bool>,
+}
+
+/// LP steps of the ranked `games` (newest first) of a player now at the current rank,
+/// oldest first. Each step comes with the `gameCreation` of its match.
+///
+/// `snapshots` (oldest first) pin the rank between games: a game with a snapshot after
+/// it and one before it, with no other game in between, is exact. The others assume a
+/// flat 15 LP, counted back from the last known rank. Either way the LP is moved along
+/// the ladder, so a step can cross into another division or tier.
+pub(crate) fn rank_steps(
+    games: &[RankedGame],
+    snapshots: &[RankSnapshot],
+    current_tier: &str,
+    current_division: &str,
+    current_lp: i32,
+) -> Vec<(RankStep, Option<i64>)> {
+    let Some(mut rank) = Rank::parse(current_tier, current_division, current_lp) else {
+        eprintln!("[DB] Unknown rank {} {}, no LP history", current_tier, current_division);
+        return Vec::new();
+    };
+    let snapshots: Vec<(Rank, i64)> = snapshots
+        .iter()
+        .filter_map(|s| Some((Rank::parse(&s.tier, &s.division, s.lp)?, s.taken_at)))
+        .collect();
+
+    let shown = games.len().min(RANK_PROGRESS_GAMES as usize);
+    let mut steps = Vec::with_capacity(shown);
+
+    for (i, game) in games[..shown].iter().enumerate() {
+        let win = game.win.unwrap_or(false);
+        // A snapshot only tells about this game while no other game can have moved the LP:
+        // after it, up to the end of the next game, and before it, from once the LP of
+        // the previous game was in.
+        let after_until = match i {
+            0 => i64::MAX,
+            _ => games[i - 1].game_end.unwrap_or(i64::MIN),
+        };
+        let before_from = match games.get(i + 1) {
+            None => i64::MIN,
+            Some(older) => older.game_end.map_or(i64::MAX, |end| end + LP_UPDATE_DELAY_MS),
+        };
+
+        let after = game.game_end.and_then(|end| {
+            snapshots
+                .iter()
+                .find(|(_, at)| *at >= end + LP_UPDATE_DELAY_MS && *at < after_until)
+        });
+        let before = game.game_end.and_then(|end| {
+            snapshots
+                .iter()
+                .rev()
+                .find(|(_, at)| *at < end && *at >= before_from)
+        });
+
+        if let Some((after, _)) = after {
+            rank = after.clone();
+        }
+        let (rank_before, exact) = match before.filter(|_| after.is_some()) {
+            Some((before, _)) => (before.clone(), true),
+            None => {
+                let delta = if win { 15 } else { -15 };
+                (Rank::from_ladder_value(rank.ladder_value() - delta, rank.tier), false)
+            }
+        };
 
-        steps.push(step);
-        lp = before;
+        let step = RankStep {
+            match_id: game.match_id.clone(),
+            label_index: 0,
+            lp_before: rank_before.lp,
+            lp_after: rank.lp,
+            lp_delta: rank.ladder_value() - rank_before.ladder_value(),
+            result: if win { "Win".to_string() } else { "Loss".to_string() },
+            tier_before: rank_before.tier.as_str().to_string(),
+            division_before: rank_before.division.to_string(),
+            tier_after: rank.tier.as_str().to_string(),
+            division_after: rank.division.to_string(),
+            exact,
+        };
+        steps.push((step, game.game_creation));
+
+        rank = rank_before;
+    }
+
+    steps.reverse();
+    for (i, (step, _)) in steps.iter_mut().enumerate() {
+        step.label_index = (i + 1) as i32;
+    }
+    steps
+}
+
+pub async fn get_backfill_checkpoint(
+    pool: &PgPool,
+    puuid: &str,
+) -> Result<Option<BackfillCheckpoint>> {
+    let row = sqlx::query_as::<_, BackfillCheckpoint>(
+        r#"
+        SELECT puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+               oldest_game_creation, matches_fetched, matches_skipped, completed
+        FROM public.backfill_checkpoints
+        WHERE puuid = $1
+        "#
+    )
+    .bind(puuid)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn save_backfill_checkpoint(
+    pool: &PgPool,
+    checkpoint: &BackfillCheckpoint,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.backfill_checkpoints
+        (puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+         oldest_game_creation, matches_fetched, matches_skipped, completed, updated_at)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10, now())
+        ON CONFLICT (puuid) DO UPDATE
+        SET regional = EXCLUDED.regional,
+            anchor_time = EXCLUDED.anchor_time,
+            cutoff_time = EXCLUDED.cutoff_time,
+            max_matches = EXCLUDED.max_matches,
+            next_start = EXCLUDED.next_start,
+            oldest_game_creation = EXCLUDED.oldest_game_creation,
+            matches_fetched = EXCLUDED.matches_fetched,
+            matches_skipped = EXCLUDED.matches_skipped,
+            completed = EXCLUDED.completed,
+            updated_at = now()
+        "#
+    )
+    .bind(&checkpoint.puuid)
+    .bind(&checkpoint.regional)
+    .bind(checkpoint.anchor_time)
+    .bind(checkpoint.cutoff_time)
+    .bind(checkpoint.max_matches)
+    .bind(checkpoint.next_start)
+    .bind(checkpoint.oldest_game_creation)
+    .bind(checkpoint.matches_fetched)
+    .bind(checkpoint.matches_skipped)
+    .bind(checkpoint.completed)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+pub(crate) struct StoredMatch {
+    #[sqlx(rename = "matchId")]
+    match_id: String,
+    participants: Option<Value>,
+    #[sqlx(rename = "gameCreation")]
+    game_creation: Option<i64>,
+    #[sqlx(rename = "gameDuration")]
+    game_duration: Option<i32>,
+    #[sqlx(rename = "queueType")]
+    queue_type: Option<i32>,
+}
+
+impl StoredMatch {
+    fn participants(&self) -> &[Value] {
+        self.participants.as_ref().and_then(|p| p.as_array()).map_or(&[], |ps| ps.as_slice())
+    }
+
+    pub(crate) fn participant_puuids(&self) -> Vec<String> {
+        self.participants()
+            .iter()
+            .filter_map(|p| p.get("puuid").and_then(|v| v.as_str()).map(String::from))
+            .collect()
     }
 
-    Ok(steps)
+    /// Scoreboard of the match. `names` maps puuids to the `Name#TAG` shown for them.
+    pub(crate) fn into_details(
+        self,
+        names: &HashMap<String, String>,
+        user_puuid: &str,
+        ddragon_version: &str,
+    ) -> MatchDetails {
+        let duration = self.game_duration.unwrap_or(0) as i64;
+        let participants = self
+            .participants()
+            .iter()
+            .map(|p| {
+                let name = p
+                    .get("puuid")
+                    .and_then(|v| v.as_str())
+                    .and_then(|puuid| names.get(puuid).cloned());
+                participant_detail(p, name, duration, ddragon_version)
+            })
+            .collect();
+
+        MatchDetails {
+            match_id: self.match_id,
+            queue_id: self.queue_type.unwrap_or(0),
+            game_creation_ms: self.game_creation.unwrap_or(0),
+            game_duration_s: duration,
+            participants,
+            user_puuid: user_puuid.to_string(),
+            ddragon_version: ddragon_version.to_string(),
+        }
+    }
+}
+
+/// Summaries of `rows` for `puuid`, following the order of `match_ids`.
+pub(crate) fn summarize_stored(
+    rows: &[StoredMatch],
+    match_ids: &[String],
+    puuid: &str,
+    ddragon_version: &str,
+) -> Vec<MatchSummary> {
+    let by_id: HashMap<&str, &StoredMatch> =
+        rows.iter().map(|row| (row.match_id.as_str(), row)).collect();
+
+    let mut summaries = Vec::with_capacity(match_ids.len());
+    for match_id in match_ids {
+        let Some(row) = by_id.get(match_id.as_str()) else { continue };
+        let participant = row
+            .participants()
+            .iter()
+            .find(|p| p.get("puuid").and_then(|v| v.as_str()) == Some(puuid));
+        let Some(participant) = participant else {
+            eprintln!("[DB] No participant {} stored for match {}", puuid, match_id);
+            continue;
+        };
+        summaries.push(summarize_participant(
+            match_id,
+            row.queue_type.unwrap_or(0),
+            row.game_creation.unwrap_or(0),
+            row.game_duration.unwrap_or(0) as i64,
+            participant,
+            ddragon_version,
+        ));
+    }
+    summaries
+}
+
+/// Summaries of `match_ids` for `puuid`, in the same order.
+/// Matches that are not stored, or that `puuid` did not play in, are left out.
+pub async fn summarize_matches(
+    pool: &PgPool,
+    match_ids: &[String],
+    puuid: &str,
+    ddragon_version: &str,
+) -> Result<Vec<MatchSummary>> {
+    let rows = sqlx::query_as::<_, StoredMatch>(
+        r#"
+        SELECT "matchId", participants, "gameCreation", "gameDuration", "queueType"
+        FROM public.match_details
+        WHERE "matchId" = ANY($1)
+        "#
+    )
+    .bind(match_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(summarize_stored(&rows, match_ids, puuid, ddragon_version))
+}
+
+/// Full scoreboard of a stored match. Names come from `summoners`, falling back to
+/// the Riot ID stored with the participant.
+pub async fn get_match_details_from_db(
+    pool: &PgPool,
+    match_id: &str,
+    user_puuid: &str,
+    ddragon_version: &str,
+) -> Result<MatchDetails> {
+    let row = sqlx::query_as::<_, StoredMatch>(
+        r#"
+        SELECT "matchId", participants, "gameCreation", "gameDuration", "queueType"
+        FROM public.match_details
+        WHERE "matchId" = $1
+        "#
+    )
+    .bind(match_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(row) = row else {
+        return Err(DianaError::MatchNotFound(match_id.to_string()).into());
+    };
+    let puuids = row.participant_puuids();
+    let names: HashMap<String, String> = sqlx::query_as::<_, (String, String, String)>(
+        r#"
+        SELECT puuid, "gameName", "tagLine"
+        FROM public.summoners
+        WHERE puuid = ANY($1)
+        "#
+    )
+    .bind(&puuids)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(puuid, name, tag)| (puuid, format!("{}#{}", name, tag)))
+    .collect();
+
+    Ok(row.into_details(&names, user_puuid, ddragon_version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(match_id: &str, game_end: i64, win: bool) -> RankedGame {
+        RankedGame {
+            match_id: match_id.to_string(),
+            game_creation: Some(game_end - 1_800_000),
+            game_end: Some(game_end),
+            win: Some(win),
+        }
+    }
+
+    fn snapshot(division: &str, lp: i32, taken_at: i64) -> RankSnapshot {
+        RankSnapshot { tier: "GOLD".to_string(), division: division.to_string(), lp, taken_at }
+    }
+
+    #[test]
+    fn snapshots_around_a_game_make_it_exact() {
+        let games = [game("G1", 1_000_000, true)];
+        let snapshots = [snapshot("II", 50, 900_000), snapshot("II", 72, 1_100_000)];
+
+        let steps = rank_steps(&games, &snapshots, "GOLD", "II", 72);
+        let (step, _) = &steps[0];
+        assert!(step.exact);
+        assert_eq!((step.lp_before, step.lp_after, step.lp_delta), (50, 72, 22));
+    }
+
+    #[test]
+    fn a_game_in_between_makes_both_estimated() {
+        // Newest first: the snapshots bracket both games, not either one alone.
+        let games = [game("G2", 2_000_000, false), game("G1", 1_000_000, true)];
+        let snapshots = [snapshot("II", 50, 900_000), snapshot("II", 70, 2_100_000)];
+
+        let steps = rank_steps(&games, &snapshots, "GOLD", "II", 70);
+        let [(older, _), (newer, _)] = steps.as_slice() else { panic!("expected two steps") };
+        assert_eq!((older.match_id.as_str(), newer.match_id.as_str()), ("G1", "G2"));
+        assert!(!older.exact && !newer.exact);
+        assert_eq!((newer.lp_before, newer.lp_after), (85, 70));
+        assert_eq!((older.lp_before, older.lp_after), (70, 85));
+    }
+
+    #[test]
+    fn a_snapshot_before_the_lp_update_is_ignored() {
+        let games = [game("G1", 1_000_000, true)];
+        let snapshots = [
+            snapshot("II", 50, 900_000),
+            snapshot("II", 50, 1_000_000 + LP_UPDATE_DELAY_MS - 1),
+        ];
+
+        let steps = rank_steps(&games, &snapshots, "GOLD", "II", 70);
+        let (step, _) = &steps[0];
+        assert!(!step.exact);
+        assert_eq!((step.lp_before, step.lp_after), (55, 70));
+    }
 }
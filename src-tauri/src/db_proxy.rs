@@ -3,9 +3,19 @@ use reqwest::Client;
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
+use std::collections::HashSet;
 use std::env;
 
-use crate::models::{DbMatchRow, DbMatchRowJson, DbSummoner, RankStep};
+use crate::db::{rank_steps, season_start_ms, RankedGame, RANK_PROGRESS_GAMES};
+use crate::error::DianaError;
+use crate::match_summary::MatchSummary;
+use crate::queues::QueueGroup;
+use crate::roles::{role_stats, ChampionRole, RoleGame, SUMMONERS_RIFT};
+use crate::models::{
+    BackfillCheckpoint, DbMatchRow, DbMatchRowJson, DbSummoner, DbTeammate, GameResult,
+    MatchupStats, RankSnapshot, RankStep, RoleStats, Streaks, TimelineFrame,
+};
+use crate::store::{NewMatchDetails, NewSummoner};
 
 /// Get the proxy base URL from runtime environment variable or compile-time default
 pub fn proxy_base_url() -> String {
@@ -26,50 +36,43 @@ pub struct ProxyPool {
     client: Client,
 }
 
+/// A transaction against the proxy.
+///
+/// Writes are buffered and sent as one batch by [`ProxyTx::commit`], which the proxy
+/// applies in a single database transaction: all of it or nothing. Dropping the
+/// transaction without committing discards the writes.
+///
+/// Because nothing reaches the database before the commit, [`insert_match_details_tx`]
+/// returns a provisional negative `mid`. Later writes in the same transaction can use
+/// it like a real one.
 pub struct ProxyTx {
     client: Client,
+    operations: Vec<Value>,
+    matches: i64,
 }
 
-pub async fn init_pool() -> Result<ProxyPool> {
-    let client = Client::builder().user_agent("Diana/0.1.0").build()?;
-    
-    let health_url = proxy_health_url();
-    match client.get(&health_url).timeout(std::time::Duration::from_secs(5)).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                println!("[DbProxy] Database proxy is available");
-            } else {
-                return Err(anyhow!("Database proxy returned status {}", response.status()));
-            }
-        }
-        Err(e) => {
-            return Err(anyhow!("Failed to connect to database proxy: {}", e));
-        }
-    }
-    
-    Ok(ProxyPool { client })
+/// Store backed by diana-proxy, sharing the app's HTTP client. Whether the proxy is up
+/// is tracked by the health monitor in [`crate::app_state`].
+pub fn init_pool(client: Client) -> ProxyPool {
+    ProxyPool { client }
 }
 
 pub async fn upsert_summoner(
     pool: &ProxyPool,
-    puuid: &str,
-    game_name: &str,
-    tag_line: &str,
-    region: &str,
-    tier: Option<&str>,
-    rank: Option<&str>,
-    lp: Option<i32>,
+    summoner: NewSummoner<'_>,
 ) -> Result<()> {
     let url = format!("{}/db/summoners", proxy_base_url());
     
     let body = json!({
-        "puuid": puuid,
-        "gameName": game_name,
-        "tagLine": tag_line,
-        "region": region,
-        "tier": tier,
-        "rank": rank,
-        "lp": lp
+        "puuid": summoner.puuid,
+        "gameName": summoner.game_name,
+        "tagLine": summoner.tag_line,
+        "region": summoner.region,
+        "matchRegionPrefix": summoner.match_region_prefix,
+        "regionGroup": summoner.region_group,
+        "tier": summoner.tier,
+        "rank": summoner.rank,
+        "lp": summoner.lp
     });
     
     let response = pool.client
@@ -80,7 +83,7 @@ pub async fn upsert_summoner(
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     Ok(())
@@ -97,7 +100,7 @@ pub async fn get_summoner(pool: &ProxyPool, puuid: &str) -> Result<Option<DbSumm
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     let text = response.text().await?;
@@ -116,7 +119,7 @@ pub async fn match_exists(pool: &ProxyPool, match_id: &str) -> Result<bool> {
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     #[derive(Deserialize)]
@@ -128,14 +131,73 @@ pub async fn match_exists(pool: &ProxyPool, match_id: &str) -> Result<bool> {
     Ok(result.exists)
 }
 
-pub async fn latest_match_for_puuid(pool: &ProxyPool, puuid: &str) -> Result<Option<String>> {
+/// Which of `match_ids` are already stored, in one request.
+pub async fn matches_exist(pool: &ProxyPool, match_ids: &[String]) -> Result<HashSet<String>> {
+    if match_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let url = format!("{}/db/matches/exists", proxy_base_url());
+
+    let response = pool
+        .client
+        .post(&url)
+        .json(&json!({ "matchIds": match_ids }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    #[derive(Deserialize)]
+    struct ExistsResponse {
+        existing: Vec<String>,
+    }
+
+    let result: ExistsResponse = response.json().await?;
+    Ok(result.existing.into_iter().collect())
+}
+
+/// Earliest `gameCreation` among the stored ones of `match_ids`.
+pub async fn oldest_game_creation(pool: &ProxyPool, match_ids: &[String]) -> Result<Option<i64>> {
+    if match_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let url = format!("{}/db/matches/oldest-creation", proxy_base_url());
+
+    let response = pool
+        .client
+        .post(&url)
+        .json(&json!({ "matchIds": match_ids }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    #[derive(Deserialize)]
+    struct OldestResponse {
+        oldest: Option<i64>,
+    }
+
+    let result: OldestResponse = response.json().await?;
+    Ok(result.oldest)
+}
+
+/// Most recent stored match of `puuid`, the anchor for incremental syncs.
+pub async fn latest_match_for_puuid(pool: &ProxyPool, puuid: &str) -> Result<Option<DbMatchRow>> {
     let url = format!("{}/db/matches/{}/latest", proxy_base_url(), puuid);
     
     let response = pool.client.get(&url).send().await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     let text = response.text().await?;
@@ -144,8 +206,7 @@ pub async fn latest_match_for_puuid(pool: &ProxyPool, puuid: &str) -> Result<Opt
     }
     
     let match_row_json: DbMatchRowJson = serde_json::from_str(&text)?;
-    let match_row: DbMatchRow = match_row_json.into();
-    Ok(Some(match_row.matchId))
+    Ok(Some(match_row_json.into()))
 }
 
 pub async fn get_recent_matches(pool: &ProxyPool, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
@@ -158,7 +219,7 @@ pub async fn get_recent_matches(pool: &ProxyPool, puuid: &str, limit: i64) -> Re
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
         eprintln!("[DB_PROXY] ❌ Error response: {}", error_text);
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     let text = response.text().await?;
@@ -179,167 +240,170 @@ pub async fn get_recent_matches(pool: &ProxyPool, puuid: &str, limit: i64) -> Re
     Ok(matches)
 }
 
-pub async fn insert_match_details_tx(
-    _tx: &mut ProxyTx,
-    match_id: &str,
-    entry_puuid: &str,
-    game_version: Option<&str>,
-    game_creation: Option<i64>,
-    game_start: Option<i64>,
-    game_end: Option<i64>,
-    game_duration: Option<i32>,
-    game_mode: Option<&str>,
-    game_type: Option<&str>,
-    queue_type: Option<i32>,
-    map_name: Option<i32>,
-    participants: &Value,
-    teams: &Value,
-) -> Result<i64> {
-    let url = format!("{}/db/matches", proxy_base_url());
-    
-    let body = json!({
-        "matchId": match_id,
-        "entryPuuid": entry_puuid,
-        "gameVersion": game_version,
-        "gameCreation": game_creation,
-        "gameStart": game_start,
-        "gameEnd": game_end,
-        "gameDuration": game_duration,
-        "gameMode": game_mode,
-        "gameType": game_type,
-        "queueType": queue_type,
-        "mapName": map_name,
-        "participants": participants,
-        "teams": teams
-    });
-    
-    let response = _tx.client
-        .post(&url)
-        .json(&body)
-        .send()
-        .await?;
-    
+/// Stored matches of `puuid` that have no timeline frames yet, newest first.
+pub async fn get_matches_missing_timeline(
+    pool: &ProxyPool,
+    puuid: &str,
+    limit: i64,
+) -> Result<Vec<DbMatchRow>> {
+    let url = format!(
+        "{}/db/matches/{}/missing-timelines?limit={}",
+        proxy_base_url(),
+        puuid,
+        limit
+    );
+
+    let response = pool.client.get(&url).send().await?;
+
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
-    }
-    
-    let response_text = response.text().await?;
-    eprintln!("[DB_PROXY] 📄 Insert response body: {}", response_text);
-    
-    #[derive(Deserialize)]
-    struct InsertResponse {
-        mid: Option<serde_json::Value>,
+        return Err(DianaError::Database(error_text).into());
     }
-    
-    let result: InsertResponse = serde_json::from_str(&response_text)
-        .map_err(|e| anyhow!("Failed to parse insert response JSON: {} - Response was: {}", e, response_text))?;
-    
-    let mid = match result.mid {
-        Some(serde_json::Value::Number(n)) => n.as_i64().unwrap_or(0),
-        Some(serde_json::Value::String(s)) => s.parse::<i64>().unwrap_or(0),
-        _ => 0,
-    };
-    
-    if mid == 0 {
-        eprintln!("[DB_PROXY] ❌ Match insertion returned mid=0 for match {}, this indicates a database issue", match_id);
-        return Err(anyhow!("Match insertion failed: no mid returned for match {}", match_id));
+
+    let matches_json: Vec<DbMatchRowJson> = response.json().await?;
+    Ok(matches_json.into_iter().map(|m| m.into()).collect())
+}
+
+pub async fn mark_timeline_unavailable(pool: &ProxyPool, mid: i64, reason: &str) -> Result<()> {
+    let url = format!("{}/db/timeline-unavailable/{}", proxy_base_url(), mid);
+
+    let response = pool.client.put(&url).json(&json!({ "reason": reason })).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
     }
-    
-    eprintln!("[DB_PROXY] ✅ Match {} inserted/updated with mid: {}", match_id, mid);
-    Ok(mid)
+
+    Ok(())
+}
+
+pub async fn insert_match_details_tx(
+    tx: &mut ProxyTx,
+    row: NewMatchDetails<'_>,
+) -> Result<i64> {
+    tx.matches += 1;
+    let match_ref = tx.matches;
+
+    tx.operations.push(json!({
+        "type": "match",
+        "ref": match_ref,
+        "matchId": row.match_id,
+        "entryPuuid": row.entry_puuid,
+        "gameVersion": row.game_version,
+        "gameCreation": row.game_creation,
+        "gameStart": row.game_start,
+        "gameEnd": row.game_end,
+        "gameDuration": row.game_duration,
+        "gameMode": row.game_mode,
+        "gameType": row.game_type,
+        "queueType": row.queue_type,
+        "mapName": row.map_name,
+        "participants": row.participants,
+        "teams": row.teams
+    }));
+
+    Ok(-match_ref)
 }
 
-pub async fn insert_timeline_frame_tx(
-    _tx: &mut ProxyTx,
+pub async fn insert_timeline_frames_tx(
+    tx: &mut ProxyTx,
     mid: i64,
     entry_participant_id: &str,
-    frame_index: Option<i32>,
-    timestamp: Option<i64>,
-    participant_frames: &Value,
-    events: &Value,
-) -> Result<i64> {
-    let url = format!("{}/db/timeline-frames", proxy_base_url());
-    eprintln!("[DB_PROXY] ➡️  POST {} (mid: {}, participant: {}, frame: {:?})", 
-              url, mid, entry_participant_id, frame_index);
-    
-    let body = json!({
-        "mid": mid,
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    let mut op = json!({
+        "type": "timelineFrames",
         "entryParticipantId": entry_participant_id,
-        "frameIndex": frame_index,
-        "timestamp": timestamp,
-        "participantFrames": participant_frames,
-        "events": events
+        "frames": frames
     });
-    
-    let response = _tx.client
+    // Negative mids are matches inserted earlier in this transaction.
+    if mid < 0 {
+        op["matchRef"] = json!(-mid);
+    } else {
+        op["mid"] = json!(mid);
+    }
+    tx.operations.push(op);
+    Ok(())
+}
+
+/// Stores all frames of an already stored match in one request.
+pub async fn insert_timeline_frames(
+    pool: &ProxyPool,
+    mid: i64,
+    entry_participant_id: &str,
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    let url = format!("{}/db/timeline-frames/batch", proxy_base_url());
+    eprintln!("[DB_PROXY] ➡️  POST {} (mid: {}, {} frames)", url, mid, frames.len());
+
+    let response = pool
+        .client
         .post(&url)
-        .json(&body)
+        .json(&json!({
+            "mid": mid,
+            "entryParticipantId": entry_participant_id,
+            "frames": frames
+        }))
         .send()
         .await?;
-    
-    eprintln!("[DB_PROXY] ⬅️  POST {} -> HTTP {}", url, response.status());
-    
+
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        eprintln!("[DB_PROXY] ❌ Timeline frame insertion failed: {}", error_text);
-        return Err(anyhow!("Database proxy error for timeline frame: {}", error_text));
+        let error_text = body_error_text(response).await;
+        eprintln!("[DB_PROXY] ❌ Timeline frames insertion failed: {}", error_text);
+        return Err(DianaError::Database(format!("timeline frames: {}", error_text)).into());
     }
-    
-    let response_text = response.text().await?;
-    eprintln!("[DB_PROXY] 📄 Timeline insert response body: {}", response_text);
-    
-    #[derive(Deserialize)]
-    struct InsertResponse {
-        tid: Option<serde_json::Value>,
-    }
-    
-    let result: InsertResponse = serde_json::from_str(&response_text)
-        .map_err(|e| anyhow!("Failed to parse timeline insert response JSON: {} - Response was: {}", e, response_text))?;
-    
-    let tid = match result.tid {
-        Some(serde_json::Value::Number(n)) => n.as_i64().unwrap_or(0),
-        Some(serde_json::Value::String(s)) => s.parse::<i64>().unwrap_or(0),
-        _ => 0,
-    };
-    
-    Ok(tid)
+    Ok(())
 }
 
-pub async fn timeline_frame_exists_tx(
-    _tx: &mut ProxyTx,
-    mid: i64,
-    frame_index: i32,
-) -> Result<bool> {
-    let url = format!("{}/db/timeline-frames/{}/{}/exists", proxy_base_url(), mid, frame_index);
-    
-    let response = _tx.client.get(&url).send().await?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+/// Error text of a failed write. Whole timelines are sent in one body, so a 413 names
+/// the proxy setting to raise instead of passing on Express' message.
+async fn body_error_text(response: reqwest::Response) -> String {
+    if response.status() == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+        return "request body exceeds the proxy's JSON_BODY_LIMIT".to_string();
     }
-    
-    #[derive(Deserialize)]
-    struct ExistsResponse {
-        exists: bool,
+    response.text().await.unwrap_or_default()
+}
+
+/// `queue` (the cache key) and `queueIds` (left out for every queue) query parameters.
+fn queue_params(queue: QueueGroup) -> Vec<(&'static str, String)> {
+    let mut params = vec![("queue", queue.as_str().to_string())];
+    if let Some(ids) = queue.queue_ids() {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        params.push(("queueIds", ids.join(",")));
     }
-    
-    let result: ExistsResponse = response.json().await?;
-    Ok(result.exists)
+    params
 }
 
 pub async fn compute_player_summary(
     pool: &ProxyPool,
     puuid: &str,
-) -> Result<(i32, i32, i32, f32, f32, i32, serde_json::Value)> {
+    queue: QueueGroup,
+) -> Result<(i32, i32, i32, f32, f32, Streaks, serde_json::Value)> {
+    let url = format!("{}/db/summaries/{}/results", proxy_base_url(), puuid);
+
+    let response = pool.client.get(&url).query(&queue_params(queue)).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let results: Vec<GameResult> = response.json().await?;
+    let streaks = Streaks::from_results(&results, season_start_ms());
+
     let url = format!("{}/db/summaries/{}", proxy_base_url(), puuid);
-    
-    let response = pool.client.get(&url).send().await?;
+
+    let response = pool
+        .client
+        .post(&url)
+        .query(&queue_params(queue))
+        .json(&streaks)
+        .send()
+        .await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     #[derive(Deserialize)]
@@ -349,7 +413,6 @@ pub async fn compute_player_summary(
         losses: i32,
         kda: f32,
         winrate: f32,
-        streak: i32,
         top_champs: serde_json::Value,
     }
     
@@ -360,7 +423,7 @@ pub async fn compute_player_summary(
         result.losses,
         result.kda,
         result.winrate,
-        result.streak,
+        streaks,
         result.top_champs,
     ))
 }
@@ -368,20 +431,46 @@ pub async fn compute_player_summary(
 pub async fn get_daily_activity(
     pool: &ProxyPool,
     puuid: &str,
+    queue: QueueGroup,
 ) -> Result<Vec<crate::models::DailyActivityEntry>> {
     let url = format!("{}/db/activity/{}", proxy_base_url(), puuid);
     
-    let response = pool.client.get(&url).send().await?;
+    let response = pool.client.get(&url).query(&queue_params(queue)).send().await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
     let activities: Vec<crate::models::DailyActivityEntry> = response.json().await?;
     Ok(activities)
 }
 
+pub async fn save_rank_snapshot(
+    pool: &ProxyPool,
+    puuid: &str,
+    snapshot: &RankSnapshot,
+) -> Result<()> {
+    let url = format!("{}/db/rank-snapshots/{}", proxy_base_url(), puuid);
+
+    let response = pool.client.post(&url).json(snapshot).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct RankHistory {
+    games: Vec<RankedGame>,
+    snapshots: Vec<RankSnapshot>,
+}
+
+/// The proxy hands over the ranked games and snapshots, the steps are worked out here
+/// like for the other stores and sent back to be cached.
 pub async fn compute_rank_progress_and_cache(
     pool: &ProxyPool,
     puuid: &str,
@@ -390,72 +479,240 @@ pub async fn compute_rank_progress_and_cache(
     current_lp: i32,
 ) -> Result<Vec<RankStep>> {
     let url = format!(
-        "{}/db/rank-progress/{}?currentTier={}&currentDivision={}&currentLp={}",
-        proxy_base_url(), puuid, current_tier, current_division, current_lp
+        "{}/db/rank-history/{}?limit={}",
+        proxy_base_url(), puuid, RANK_PROGRESS_GAMES + 1
+    );
+
+    let response = pool.client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let history: RankHistory = response.json().await?;
+    let steps = rank_steps(
+        &history.games,
+        &history.snapshots,
+        current_tier,
+        current_division,
+        current_lp,
+    );
+
+    let cached: Vec<Value> = steps
+        .iter()
+        .map(|(step, game_creation)| {
+            let mut row = serde_json::to_value(step)?;
+            row["game_creation"] = json!(game_creation);
+            Ok(row)
+        })
+        .collect::<Result<_, serde_json::Error>>()?;
+    let url = format!("{}/db/rank-progress/{}", proxy_base_url(), puuid);
+    let response = pool.client.put(&url).json(&json!({ "steps": cached })).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    Ok(steps.into_iter().map(|(step, _)| step).collect())
+}
+
+#[derive(Deserialize)]
+struct RoleGames {
+    games: Vec<RoleGame>,
+    champion_roles: Vec<ChampionRole>,
+}
+
+/// The proxy hands over the games and, for champions whose role is missing somewhere,
+/// how often they are played in each role. The stats are worked out here.
+pub async fn get_role_stats(
+    pool: &ProxyPool,
+    puuid: &str,
+    queue: QueueGroup,
+) -> Result<Vec<RoleStats>> {
+    let url = format!(
+        "{}/db/role-games/{}?mapId={}",
+        proxy_base_url(), puuid, SUMMONERS_RIFT
     );
+
+    let response = pool.client.get(&url).query(&queue_params(queue)).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let role_games: RoleGames = response.json().await?;
+    Ok(role_stats(&role_games.games, &role_games.champion_roles))
+}
+
+pub async fn get_matchup_stats(
+    pool: &ProxyPool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<MatchupStats>> {
+    let url = format!("{}/db/matchups/{}?minGames={}", proxy_base_url(), puuid, min_games);
+
+    let response = pool.client.get(&url).query(&queue_params(queue)).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let matchups: Vec<MatchupStats> = response.json().await?;
+    Ok(matchups)
+}
+
+pub async fn get_teammates(
+    pool: &ProxyPool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<DbTeammate>> {
+    let url = format!("{}/db/teammates/{}?minGames={}", proxy_base_url(), puuid, min_games);
+
+    let response = pool.client.get(&url).query(&queue_params(queue)).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let teammates: Vec<DbTeammate> = response.json().await?;
+    Ok(teammates)
+}
+
+pub async fn get_backfill_checkpoint(
+    pool: &ProxyPool,
+    puuid: &str,
+) -> Result<Option<BackfillCheckpoint>> {
+    let url = format!("{}/db/backfill/{}", proxy_base_url(), puuid);
     
     let response = pool.client.get(&url).send().await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(anyhow!("Database proxy error: {}", error_text));
+        return Err(DianaError::Database(error_text).into());
     }
     
-    let steps: Vec<RankStep> = response.json().await?;
-    Ok(steps)
+    let text = response.text().await?;
+    if text == "null" {
+        return Ok(None);
+    }
+    
+    let checkpoint: BackfillCheckpoint = serde_json::from_str(&text)?;
+    Ok(Some(checkpoint))
+}
+
+pub async fn save_backfill_checkpoint(
+    pool: &ProxyPool,
+    checkpoint: &BackfillCheckpoint,
+) -> Result<()> {
+    let url = format!("{}/db/backfill", proxy_base_url());
+    
+    let response = pool.client.put(&url).json(checkpoint).send().await?;
+    
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+    
+    Ok(())
+}
+
+/// Newest migration applied to the proxy's database, `None` if it was never migrated
+/// by sqlx (e.g. set up by docker-compose).
+pub async fn schema_version(pool: &ProxyPool) -> Result<Option<i64>> {
+    let url = format!("{}/db/schema-version", proxy_base_url());
+
+    let response = pool.client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(DianaError::Database(error_text).into());
+    }
+
+    let body: Value = response.json().await?;
+    Ok(body.get("version").and_then(|v| v.as_i64()))
 }
 
 impl ProxyPool {
     pub async fn begin(&self) -> Result<ProxyTx> {
         Ok(ProxyTx {
             client: self.client.clone(),
+            operations: Vec::new(),
+            matches: 0,
         })
     }
 }
 
 impl ProxyTx {
+    /// Sends the buffered writes to the proxy, which applies all of them or none.
     pub async fn commit(self) -> Result<()> {
+        if self.operations.is_empty() {
+            return Ok(());
+        }
+
+        let url = format!("{}/db/ingest", proxy_base_url());
+        eprintln!("[DB_PROXY] ➡️  POST {} ({} operations)", url, self.operations.len());
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&json!({ "operations": self.operations }))
+            .send()
+            .await?;
+
+        eprintln!("[DB_PROXY] ⬅️  POST {} -> HTTP {}", url, response.status());
+
+        if !response.status().is_success() {
+            let error_text = body_error_text(response).await;
+            eprintln!("[DB_PROXY] ❌ Transaction rolled back: {}", error_text);
+            return Err(DianaError::Database(format!("transaction rolled back: {}", error_text)).into());
+        }
         Ok(())
     }
 }
 
-pub async fn summarize_match_from_db(
+/// Summaries of `match_ids` for `puuid` in one request, in the same order.
+/// Matches that are not stored, or that `puuid` did not play in, are left out.
+pub async fn summarize_matches(
     pool: &ProxyPool,
-    match_id: &str,
+    match_ids: &[String],
     puuid: &str,
     ddragon_version: &str,
-) -> Result<crate::match_summary::MatchSummary> {
-    let url = format!(
-        "{}/db/match-summary/{}/{}/{}",
-        proxy_base_url(), match_id, puuid, ddragon_version
-    );
-    eprintln!("[DB_PROXY] ➡️  GET {} (match: {}, puuid: {})", url, match_id, puuid);
-    
-    let response = pool.client.get(&url).send().await?;
-    eprintln!("[DB_PROXY] ⬅️  GET {} -> HTTP {}", url, response.status());
-    
+) -> Result<Vec<MatchSummary>> {
+    if match_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let url = format!("{}/db/match-summaries", proxy_base_url());
+    eprintln!("[DB_PROXY] ➡️  POST {} ({} matches, puuid: {})", url, match_ids.len(), puuid);
+
+    let response = pool
+        .client
+        .post(&url)
+        .json(&json!({
+            "matchIds": match_ids,
+            "puuid": puuid,
+            "ddragonVersion": ddragon_version
+        }))
+        .send()
+        .await?;
+    eprintln!("[DB_PROXY] ⬅️  POST {} -> HTTP {}", url, response.status());
+
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        eprintln!("[DB_PROXY] ❌ Error response for match {}: {}", match_id, error_text);
-        return Err(anyhow!("Database proxy error for match {}: {}", match_id, error_text));
+        return Err(DianaError::Database(format!("match summaries: {}", error_text)).into());
     }
-    
+
     let text = response.text().await?;
-    eprintln!("[DB_PROXY] 📄 Raw JSON response for match {}: {}", match_id, 
-              text.chars().take(300).collect::<String>());
-    
-    let match_summary: crate::match_summary::MatchSummary = serde_json::from_str(&text)
-        .map_err(|e| {
-            eprintln!("[DB_PROXY] ❌ JSON parsing failed for match {}. Error: {}", match_id, e);
-            eprintln!("[DB_PROXY] Full response: {}", text);
-            anyhow!("Failed to parse match summary JSON for {}: {} - Response was: {}", match_id, e, text)
-        })?;
-    
-    eprintln!("[DB_PROXY] ✅ Successfully parsed match summary for {}: {} {} ({})", 
-              match_id, match_summary.champion_name, 
-              if match_summary.win { "WIN" } else { "LOSS" }, match_summary.kda);
-    
-    Ok(match_summary)
+    let summaries: Vec<MatchSummary> = serde_json::from_str(&text)
+        .map_err(|e| anyhow!("Failed to parse match summaries JSON: {} - Response was: {}", e, text))?;
+    Ok(summaries)
 }
 
 pub async fn get_match_details_from_db(
@@ -463,9 +720,6 @@ pub async fn get_match_details_from_db(
     match_id: &str,
     user_puuid: &str,
     ddragon_version: &str,
-    _client: &reqwest::Client,
-    _api_key: &str,
-    _regional: &str,
 ) -> Result<crate::models::MatchDetails> {
     let url = format!(
         "{}/db/match-details/{}/{}/{}",
@@ -477,10 +731,14 @@ pub async fn get_match_details_from_db(
     let response = pool.client.get(&url).send().await?;
     eprintln!("[DB_PROXY] ⬅️  GET {} -> HTTP {}", url, response.status());
     
+    if response.status().as_u16() == 404 {
+        return Err(DianaError::MatchNotFound(match_id.to_string()).into());
+    }
+
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
         eprintln!("[DB_PROXY] ❌ Error response for match details {}: {}", match_id, error_text);
-        return Err(anyhow!("Database proxy error for match details {}: {}", match_id, error_text));
+        return Err(DianaError::Database(format!("match details {}: {}", match_id, error_text)).into());
     }
     
     let text = response.text().await?;
@@ -499,6 +757,3 @@ pub async fn get_match_details_from_db(
     
     Ok(match_details)
 }
-
-pub type PgPool = ProxyPool;
-pub type PgTx<'a> = ProxyTx;
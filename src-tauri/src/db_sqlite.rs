@@ -0,0 +1,1119 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Pool, Sqlite, Transaction};
+use serde_json::Value;
+
+use crate::db::{
+    rank_steps, season_start_ms, summarize_stored, summary_totals, RankedGame, StoredMatch,
+    SummaryRow, RANK_PROGRESS_GAMES,
+};
+use crate::error::DianaError;
+use crate::match_summary::MatchSummary;
+use crate::queues::{QueueGroup, RANKED_SOLO};
+use crate::roles::{champions_without_role, role_stats, ChampionRole, RoleGame, SUMMONERS_RIFT};
+use crate::models::{
+    BackfillCheckpoint, DailyActivityEntry, DbMatchRow, DbSummoner, DbTeammate, GameResult,
+    MatchDetails, MatchupStats, RankSnapshot, RankStep, RoleStats, Streaks, TimelineFrame,
+};
+use crate::store::{NewMatchDetails, NewSummoner};
+
+pub type SqlitePool = Pool<Sqlite>;
+pub type SqliteTx<'a> = Transaction<'a, Sqlite>;
+
+/// File the embedded store lives in, inside the app data dir.
+pub const DATABASE_FILE: &str = "diana.sqlite3";
+
+/// SQLite flavour of the schema in `migrations/`, kept at the same versions.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Opens the database in `data_dir`, creating the file if needed.
+pub async fn init_pool(data_dir: &Path) -> Result<SqlitePool> {
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(DATABASE_FILE);
+    eprintln!("[DB_SQLITE] Opening {}", path.display());
+
+    let options = SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+/// Applies the migrations the database has not seen yet.
+pub async fn migrate(pool: &SqlitePool) -> Result<()> {
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
+
+/// Newest migration applied to the database, `None` if it was never migrated.
+pub async fn schema_version(pool: &SqlitePool) -> Result<Option<i64>> {
+    let tracked = sqlx::query_scalar::<Sqlite, bool>(
+        r#"SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')"#
+    )
+    .fetch_one(pool)
+    .await?;
+    if !tracked {
+        return Ok(None);
+    }
+
+    let version = sqlx::query_scalar::<Sqlite, Option<i64>>(
+        r#"SELECT MAX(version) FROM _sqlx_migrations WHERE success"#
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(version)
+}
+
+/// SQLite has no array parameters, lists are bound as a JSON array and read back
+/// with `json_each`.
+fn json_list<T: serde::Serialize>(values: &[T]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+pub async fn upsert_summoner(
+    pool: &SqlitePool,
+    summoner: NewSummoner<'_>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO summoners
+        (puuid, "gameName", "tagLine", region, "matchRegionPrefix", "regionGroup",
+         tier, rank, lp, "lastUpdated")
+        VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 'UNRANKED'), $8, COALESCE($9, 0),
+                CURRENT_TIMESTAMP)
+        ON CONFLICT (puuid) DO UPDATE
+        SET "gameName" = excluded."gameName",
+            "tagLine" = excluded."tagLine",
+            region = excluded.region,
+            "matchRegionPrefix" = excluded."matchRegionPrefix",
+            "regionGroup" = excluded."regionGroup",
+            tier = excluded.tier,
+            rank = excluded.rank,
+            lp = excluded.lp,
+            "lastUpdated" = CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(summoner.puuid)
+    .bind(summoner.game_name)
+    .bind(summoner.tag_line)
+    .bind(summoner.region)
+    .bind(summoner.match_region_prefix)
+    .bind(summoner.region_group)
+    .bind(summoner.tier)
+    .bind(summoner.rank)
+    .bind(summoner.lp)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_summoner(pool: &SqlitePool, puuid: &str) -> Result<Option<DbSummoner>> {
+    let row = sqlx::query_as::<_, DbSummoner>(
+        r#"
+        SELECT puuid, "gameName", "tagLine", region, "matchRegionPrefix",
+               "deepLolLink", tier, rank, lp, "currentMatchId", "discordChannelId",
+               "regionGroup", "lastUpdated", "lastMissingDataNotification"
+        FROM summoners
+        WHERE puuid = $1
+        "#
+    )
+    .bind(puuid)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn match_exists(pool: &SqlitePool, match_id: &str) -> Result<bool> {
+    let rec = sqlx::query_scalar::<Sqlite, bool>(
+        r#"SELECT EXISTS(SELECT 1 FROM match_details WHERE "matchId" = $1)"#
+    )
+    .bind(match_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(rec)
+}
+
+/// Which of `match_ids` are already stored.
+pub async fn matches_exist(pool: &SqlitePool, match_ids: &[String]) -> Result<HashSet<String>> {
+    let rows = sqlx::query_scalar::<Sqlite, String>(
+        r#"
+        SELECT "matchId" FROM match_details
+        WHERE "matchId" IN (SELECT value FROM json_each($1))
+        "#
+    )
+    .bind(json_list(match_ids))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Earliest `gameCreation` among the stored ones of `match_ids`.
+pub async fn oldest_game_creation(pool: &SqlitePool, match_ids: &[String]) -> Result<Option<i64>> {
+    let oldest = sqlx::query_scalar::<Sqlite, Option<i64>>(
+        r#"
+        SELECT MIN("gameCreation") FROM match_details
+        WHERE "matchId" IN (SELECT value FROM json_each($1))
+        "#
+    )
+    .bind(json_list(match_ids))
+    .fetch_one(pool)
+    .await?;
+    Ok(oldest)
+}
+
+/// Most recent stored match of `puuid`, the anchor for incremental syncs.
+pub async fn latest_match_for_puuid(pool: &SqlitePool, puuid: &str) -> Result<Option<DbMatchRow>> {
+    let row = sqlx::query_as::<_, DbMatchRow>(
+        r#"
+        SELECT mid, "matchId", "entryPlayerPuuid", "gameCreation"
+        FROM match_details
+        WHERE "entryPlayerPuuid" = $1
+        ORDER BY "gameCreation" DESC
+        LIMIT 1
+        "#
+    )
+    .bind(puuid)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn get_recent_matches(pool: &SqlitePool, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
+    let rows = sqlx::query_as::<_, DbMatchRow>(
+        r#"
+        SELECT mid, "matchId", "entryPlayerPuuid", "gameCreation"
+        FROM match_details
+        WHERE "entryPlayerPuuid" = $1
+        ORDER BY "gameCreation" DESC
+        LIMIT $2
+        "#
+    )
+    .bind(puuid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Stored matches of `puuid` that have no timeline frames yet, newest first.
+pub async fn get_matches_missing_timeline(
+    pool: &SqlitePool,
+    puuid: &str,
+    limit: i64,
+) -> Result<Vec<DbMatchRow>> {
+    let rows = sqlx::query_as::<_, DbMatchRow>(
+        r#"
+        SELECT md.mid, md."matchId", md."entryPlayerPuuid", md."gameCreation"
+        FROM match_details md
+        WHERE md."entryPlayerPuuid" = $1
+          AND NOT EXISTS (
+            SELECT 1 FROM match_timeline mt WHERE mt.mid = md.mid
+          )
+          AND NOT EXISTS (
+            SELECT 1 FROM timeline_unavailable tu WHERE tu.mid = md.mid
+          )
+        ORDER BY md."gameCreation" DESC
+        LIMIT $2
+        "#
+    )
+    .bind(puuid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn mark_timeline_unavailable(pool: &SqlitePool, mid: i64, reason: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO timeline_unavailable (mid, reason)
+        VALUES ($1, $2)
+        ON CONFLICT (mid) DO UPDATE SET reason = excluded.reason
+        "#
+    )
+    .bind(mid)
+    .bind(reason)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Returns the row's `mid`. A match that is already stored keeps its row.
+pub async fn insert_match_details_tx(
+    tx: &mut SqliteTx<'_>,
+    row: NewMatchDetails<'_>,
+) -> Result<i64> {
+    let rec = sqlx::query_scalar::<Sqlite, i64>(
+        r#"
+        INSERT INTO match_details
+        ("matchId", "entryPlayerPuuid", "gameVersion", "gameCreation",
+         "gameStartTime", "gameEndTime", "gameDuration", "gameMode",
+         "gameType", "queueType", "mapName", participants, teams)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
+        ON CONFLICT ("matchId") DO UPDATE SET "matchId" = excluded."matchId"
+        RETURNING mid
+        "#
+    )
+    .bind(row.match_id)
+    .bind(row.entry_puuid)
+    .bind(row.game_version)
+    .bind(row.game_creation)
+    .bind(row.game_start)
+    .bind(row.game_end)
+    .bind(row.game_duration)
+    .bind(row.game_mode)
+    .bind(row.game_type)
+    .bind(row.queue_type)
+    .bind(row.map_name)
+    .bind(row.participants)
+    .bind(row.teams)
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    insert_match_participants_tx(tx, rec).await?;
+    Ok(rec)
+}
+
+/// Fills `match_participants` from the participants JSON of a just inserted match.
+async fn insert_match_participants_tx(tx: &mut SqliteTx<'_>, mid: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO match_participants
+        (mid, puuid, team_id, champion_name, team_position, kills, deaths, assists, cs,
+         gold_earned, damage_to_champions, vision_score, win)
+        SELECT
+          md.mid,
+          json_extract(p.value, '$.puuid'),
+          json_extract(p.value, '$.teamId'),
+          json_extract(p.value, '$.championName'),
+          NULLIF(json_extract(p.value, '$.teamPosition'), ''),
+          COALESCE(json_extract(p.value, '$.kills'), 0),
+          COALESCE(json_extract(p.value, '$.deaths'), 0),
+          COALESCE(json_extract(p.value, '$.assists'), 0),
+          COALESCE(json_extract(p.value, '$.totalMinionsKilled'), 0)
+            + COALESCE(json_extract(p.value, '$.neutralMinionsKilled'), 0),
+          COALESCE(json_extract(p.value, '$.goldEarned'), 0),
+          COALESCE(json_extract(p.value, '$.totalDamageDealtToChampions'), 0),
+          COALESCE(json_extract(p.value, '$.visionScore'), 0),
+          COALESCE(json_extract(p.value, '$.win'), 0)
+        FROM match_details md, json_each(md.participants) p
+        WHERE md.mid = $1 AND json_extract(p.value, '$.puuid') IS NOT NULL
+        ON CONFLICT (mid, puuid) DO NOTHING
+        "#
+    )
+    .bind(mid)
+    .execute(tx.as_mut())
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_timeline_frames_tx(
+    tx: &mut SqliteTx<'_>,
+    mid: i64,
+    entry_participant_id: &str,
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    for frame in frames {
+        sqlx::query(
+            r#"
+            INSERT INTO match_timeline
+            (mid, "entryParticipantId", "frameIndex", "timestamp",
+             "participantFrames", events)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            "#
+        )
+        .bind(mid)
+        .bind(entry_participant_id)
+        .bind(frame.frame_index)
+        .bind(frame.timestamp)
+        .bind(&frame.participant_frames)
+        .bind(&frame.events)
+        .execute(tx.as_mut())
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stores all frames of an already stored match.
+pub async fn insert_timeline_frames(
+    pool: &SqlitePool,
+    mid: i64,
+    entry_participant_id: &str,
+    frames: Vec<TimelineFrame>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    insert_timeline_frames_tx(&mut tx, mid, entry_participant_id, frames).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Queue IDs of `queue` as a JSON list for the `$n IS NULL OR "queueType" IN (SELECT
+/// value FROM json_each($n))` filter, `None` for every queue.
+fn queue_filter(queue: QueueGroup) -> Option<String> {
+    queue.queue_ids().map(|ids| json_list(&ids))
+}
+
+pub async fn compute_player_summary(
+    pool: &SqlitePool,
+    puuid: &str,
+    queue: QueueGroup,
+) -> Result<(i32, i32, i32, f32, f32, Streaks, Value)> {
+    let row = sqlx::query_as::<_, SummaryRow>(
+        r#"
+        WITH ranked AS (
+          SELECT
+            mp.win,
+            mp.kills AS k,
+            mp.deaths AS d,
+            mp.assists AS a,
+            mp.champion_name AS champion
+          FROM match_participants mp
+          JOIN match_details md ON md.mid = mp.mid
+          WHERE mp.puuid = $1
+            AND ($2 IS NULL OR md."queueType" IN (SELECT value FROM json_each($2)))
+            AND COALESCE(md."gameDuration",0) >= 300
+        ),
+        sums AS (
+          SELECT
+            COUNT(*) AS games,
+            SUM(CASE WHEN win THEN 1 ELSE 0 END) AS wins,
+            SUM(CASE WHEN win THEN 0 ELSE 1 END) AS losses,
+            SUM(k) AS tk,
+            SUM(d) AS td,
+            SUM(a) AS ta
+          FROM ranked
+        ),
+        champs AS (
+          SELECT
+            champion,
+            COUNT(*) AS games,
+            SUM(CASE WHEN win THEN 1 ELSE 0 END) AS wins,
+            SUM(k) AS tk,
+            SUM(d) AS td,
+            SUM(a) AS ta
+          FROM ranked
+          GROUP BY champion
+          HAVING COUNT(*) >= 3
+          ORDER BY games DESC, wins DESC
+          LIMIT 10
+        )
+        SELECT
+            sums.games,
+            sums.wins,
+            sums.losses,
+            sums.tk, sums.td, sums.ta,
+            (
+                SELECT COALESCE(
+                json_group_array(json_object(
+                    'champion_name', c.champion,
+                    'games', c.games,
+                    'wins', c.wins,
+                    'winrate', CASE WHEN c.games>0
+                                    THEN CAST(ROUND(CAST(c.wins AS REAL)/c.games*100) AS INTEGER)
+                                    ELSE 0 END,
+                    'kda', CASE WHEN c.td=0
+                                THEN CAST(c.tk+c.ta AS REAL)
+                                ELSE ROUND(CAST(c.tk+c.ta AS REAL)/c.td, 2)
+                         END
+                )),
+                '[]'
+                )
+                FROM champs c
+            ) AS top_champs
+            FROM sums;
+        "#
+    )
+    .bind(puuid)
+    .bind(queue_filter(queue))
+    .fetch_one(pool)
+    .await?;
+
+    let (games, wins, losses, kda, winrate, top_champs) = summary_totals(&row);
+
+    let results = sqlx::query_as::<_, GameResult>(
+        r#"
+        SELECT mp.win,
+               md."gameCreation" AS game_creation,
+               COALESCE(md."gameDuration",0) AS game_duration
+        FROM match_participants mp
+        JOIN match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND ($2 IS NULL OR md."queueType" IN (SELECT value FROM json_each($2)))
+          AND md."gameCreation" IS NOT NULL
+        ORDER BY md."gameCreation"
+        "#
+    )
+    .bind(puuid)
+    .bind(queue_filter(queue))
+    .fetch_all(pool)
+    .await?;
+    let streaks = Streaks::from_results(&results, season_start_ms());
+
+    sqlx::query(
+        r#"
+        INSERT INTO player_summary
+        (puuid, queue, games, wins, losses, avg_kda, winrate, streak,
+         longest_win_streak, longest_loss_streak, top_champs, computed_at)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11, CURRENT_TIMESTAMP)
+        ON CONFLICT (puuid, queue) DO UPDATE
+        SET games=$3, wins=$4, losses=$5,
+            avg_kda=$6, winrate=$7, streak=$8, longest_win_streak=$9,
+            longest_loss_streak=$10, top_champs=$11, computed_at=CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(puuid)
+    .bind(queue.as_str())
+    .bind(games)
+    .bind(wins)
+    .bind(losses)
+    .bind(kda as f64)
+    .bind(winrate as f64)
+    .bind(streaks.current)
+    .bind(streaks.longest_win)
+    .bind(streaks.longest_loss)
+    .bind(&top_champs)
+    .execute(pool)
+    .await?;
+
+    Ok((
+        games,
+        wins,
+        losses,
+        (kda * 100.0).round() / 100.0,
+        (winrate * 100.0).round() / 100.0,
+        streaks,
+        top_champs,
+    ))
+}
+
+/// Games per day in the `queue` group over the last 30 days (UTC), including days without any.
+pub async fn get_daily_activity(
+    pool: &SqlitePool,
+    puuid: &str,
+    queue: QueueGroup,
+) -> Result<Vec<DailyActivityEntry>> {
+    let rows = sqlx::query_as::<_, (Option<String>, Option<i32>)>(
+        r#"
+        WITH RECURSIVE date_series(date) AS (
+            SELECT date('now', '-29 days')
+            UNION ALL
+            SELECT date(date, '+1 day') FROM date_series WHERE date < date('now')
+        ),
+        daily_counts AS (
+            SELECT
+                date(md."gameCreation" / 1000, 'unixepoch') AS game_date,
+                COUNT(*) AS games
+            FROM match_participants mp
+            JOIN match_details md ON md.mid = mp.mid
+            WHERE mp.puuid = $1
+                AND md."gameCreation" >= CAST(strftime('%s', 'now', '-30 days') AS INTEGER) * 1000
+                AND ($2 IS NULL OR md."queueType" IN (SELECT value FROM json_each($2)))
+            GROUP BY game_date
+        )
+        SELECT
+            ds.date AS date,
+            COALESCE(dc.games, 0) AS games
+        FROM date_series ds
+        LEFT JOIN daily_counts dc ON ds.date = dc.game_date
+        ORDER BY ds.date
+        "#
+    )
+    .bind(puuid)
+    .bind(queue_filter(queue))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(date, games)| DailyActivityEntry {
+            date: date.unwrap_or_default(),
+            games: games.unwrap_or(0),
+        })
+        .collect())
+}
+
+pub async fn save_rank_snapshot(
+    pool: &SqlitePool,
+    puuid: &str,
+    snapshot: &RankSnapshot,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO rank_snapshots (puuid, tier, division, lp, taken_at)
+        VALUES ($1,$2,$3,$4,$5)
+        "#
+    )
+    .bind(puuid)
+    .bind(&snapshot.tier)
+    .bind(&snapshot.division)
+    .bind(snapshot.lp)
+    .bind(snapshot.taken_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn compute_rank_progress_and_cache(
+    pool: &SqlitePool,
+    puuid: &str,
+    current_tier: &str,
+    current_division: &str,
+    current_lp: i32,
+) -> Result<Vec<RankStep>> {
+    let games = sqlx::query_as::<_, RankedGame>(
+        r#"
+        SELECT m."matchId" AS match_id,
+               m."gameCreation" AS game_creation,
+               m."gameCreation" + COALESCE(m."gameDuration", 0) * 1000 AS game_end,
+               mp.win
+        FROM match_participants mp
+        JOIN match_details m ON m.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND m."queueType" = $2
+        ORDER BY m."gameCreation" DESC
+        LIMIT $3
+        "#
+    )
+    .bind(puuid)
+    .bind(RANKED_SOLO)
+    .bind(RANK_PROGRESS_GAMES + 1)
+    .fetch_all(pool)
+    .await?;
+
+    // From the last snapshot before the oldest game on.
+    let oldest = games.last().and_then(|g| g.game_creation).unwrap_or(0);
+    let snapshots = sqlx::query_as::<_, RankSnapshot>(
+        r#"
+        SELECT tier, division, lp, taken_at
+        FROM rank_snapshots
+        WHERE puuid = $1
+          AND taken_at >= COALESCE(
+            (SELECT MAX(taken_at) FROM rank_snapshots WHERE puuid = $1 AND taken_at < $2),
+            $2)
+        ORDER BY taken_at
+        "#
+    )
+    .bind(puuid)
+    .bind(oldest)
+    .fetch_all(pool)
+    .await?;
+
+    let steps = rank_steps(&games, &snapshots, current_tier, current_division, current_lp);
+    for (step, game_creation) in &steps {
+        sqlx::query(
+            r#"
+            INSERT INTO match_rank_progress
+            (puuid, match_id, game_creation, lp_before, lp_after, lp_delta,
+             tier_before, division_before, tier_after, division_after, exact)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11)
+            ON CONFLICT (puuid, match_id) DO UPDATE
+            SET lp_before = excluded.lp_before,
+                lp_after = excluded.lp_after,
+                lp_delta = excluded.lp_delta,
+                tier_before = excluded.tier_before,
+                division_before = excluded.division_before,
+                tier_after = excluded.tier_after,
+                division_after = excluded.division_after,
+                exact = excluded.exact,
+                computed_at = CURRENT_TIMESTAMP
+            WHERE NOT match_rank_progress.exact
+            "#
+        )
+        .bind(puuid)
+        .bind(&step.match_id)
+        .bind(game_creation)
+        .bind(step.lp_before)
+        .bind(step.lp_after)
+        .bind(step.lp_delta)
+        .bind(&step.tier_before)
+        .bind(&step.division_before)
+        .bind(&step.tier_after)
+        .bind(&step.division_after)
+        .bind(step.exact)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(steps.into_iter().map(|(step, _)| step).collect())
+}
+
+pub async fn get_role_stats(pool: &SqlitePool, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>> {
+    let games = sqlx::query_as::<_, RoleGame>(
+        r#"
+        SELECT mp.team_position, mp.champion_name,
+               (SELECT group_concat(o.team_position, ',')
+                FROM match_participants o
+                WHERE o.mid = mp.mid AND o.team_id = mp.team_id AND o.puuid <> mp.puuid
+               ) AS teammate_positions,
+               mp.kills, mp.deaths, mp.assists, mp.cs, mp.gold_earned, mp.vision_score, mp.win,
+               md."gameDuration" AS game_duration,
+               (SELECT SUM(o.kills)
+                FROM match_participants o
+                WHERE o.mid = mp.mid AND o.team_id = mp.team_id
+               ) AS team_kills
+        FROM match_participants mp
+        JOIN match_details md ON md.mid = mp.mid
+        WHERE mp.puuid = $1
+          AND md."mapName" = $2
+          AND COALESCE(md."gameDuration",0) >= 300
+          AND ($3 IS NULL OR md."queueType" IN (SELECT value FROM json_each($3)))
+        "#
+    )
+    .bind(puuid)
+    .bind(SUMMONERS_RIFT)
+    .bind(queue_filter(queue))
+    .fetch_all(pool)
+    .await?;
+
+    let champions = champions_without_role(&games);
+    let champion_roles = if champions.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as::<_, ChampionRole>(
+            r#"
+            SELECT champion_name, team_position, COUNT(*) AS games
+            FROM match_participants
+            WHERE champion_name IN (SELECT value FROM json_each($1))
+              AND team_position IS NOT NULL
+            GROUP BY champion_name, team_position
+            "#
+        )
+        .bind(json_list(&champions))
+        .fetch_all(pool)
+        .await?
+    };
+
+    Ok(role_stats(&games, &champion_roles))
+}
+
+pub async fn get_matchup_stats(
+    pool: &SqlitePool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<MatchupStats>> {
+    let rows = sqlx::query_as::<_, MatchupStats>(
+        r#"
+        SELECT me.champion_name,
+               opp.champion_name AS opponent_champion_name,
+               me.team_position AS role,
+               COUNT(*) AS games,
+               SUM(me.win) AS wins,
+               100.0 * SUM(me.win) / COUNT(*) AS winrate,
+               AVG((me.kills + me.assists) * 1.0 / MAX(me.deaths, 1)
+                   - (opp.kills + opp.assists) * 1.0 / MAX(opp.deaths, 1)) AS avg_kda_diff,
+               AVG(me.gold_earned - opp.gold_earned) AS avg_gold_diff
+        FROM match_participants me
+        JOIN match_details md ON md.mid = me.mid
+        JOIN match_participants opp
+          ON opp.mid = me.mid
+         AND opp.team_id <> me.team_id
+         AND opp.team_position = me.team_position
+        WHERE me.puuid = $1
+          AND me.champion_name IS NOT NULL
+          AND opp.champion_name IS NOT NULL
+          AND COALESCE(md."gameDuration",0) >= 300
+          AND ($2 IS NULL OR md."queueType" IN (SELECT value FROM json_each($2)))
+        GROUP BY me.champion_name, opp.champion_name, me.team_position
+        HAVING COUNT(*) >= $3
+        ORDER BY me.champion_name, games DESC, opp.champion_name
+        "#
+    )
+    .bind(puuid)
+    .bind(queue_filter(queue))
+    .bind(min_games)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_teammates(
+    pool: &SqlitePool,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<DbTeammate>> {
+    let rows = sqlx::query_as::<_, DbTeammate>(
+        r#"
+        WITH games AS (
+            SELECT mp.mid, mp.team_id, mp.win, md."gameCreation" AS game_creation
+            FROM match_participants mp
+            JOIN match_details md ON md.mid = mp.mid
+            WHERE mp.puuid = $1
+              AND COALESCE(md."gameDuration",0) >= 300
+              AND ($2 IS NULL OR md."queueType" IN (SELECT value FROM json_each($2)))
+        ),
+        totals AS (
+            SELECT COUNT(*) AS games, COALESCE(SUM(win), 0) AS wins
+            FROM games
+        ),
+        mates AS (
+            -- With MAX(), SQLite takes the bare g.mid from the row holding the maximum.
+            SELECT mate.puuid,
+                   COUNT(*) AS games,
+                   SUM(g.win) AS wins,
+                   MAX(g.game_creation) AS last_played,
+                   g.mid AS last_mid
+            FROM games g
+            JOIN match_participants mate
+              ON mate.mid = g.mid
+             AND mate.team_id = g.team_id
+             AND mate.puuid <> $1
+            GROUP BY mate.puuid
+            HAVING COUNT(*) >= $3
+        )
+        SELECT m.puuid,
+               m.games,
+               m.wins,
+               totals.games AS total_games,
+               totals.wins AS total_wins,
+               (
+                   SELECT NULLIF(json_extract(p.value, '$.riotIdGameName'), '')
+                          || '#' || NULLIF(json_extract(p.value, '$.riotIdTagline'), '')
+                   FROM match_details md, json_each(md.participants) p
+                   WHERE md.mid = m.last_mid AND json_extract(p.value, '$.puuid') = m.puuid
+               ) AS riot_id
+        FROM mates m
+        CROSS JOIN totals
+        ORDER BY m.games DESC, m.wins DESC, m.puuid
+        "#
+    )
+    .bind(puuid)
+    .bind(queue_filter(queue))
+    .bind(min_games)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_backfill_checkpoint(
+    pool: &SqlitePool,
+    puuid: &str,
+) -> Result<Option<BackfillCheckpoint>> {
+    let row = sqlx::query_as::<_, BackfillCheckpoint>(
+        r#"
+        SELECT puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+               oldest_game_creation, matches_fetched, matches_skipped, completed
+        FROM backfill_checkpoints
+        WHERE puuid = $1
+        "#
+    )
+    .bind(puuid)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+pub async fn save_backfill_checkpoint(
+    pool: &SqlitePool,
+    checkpoint: &BackfillCheckpoint,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO backfill_checkpoints
+        (puuid, regional, anchor_time, cutoff_time, max_matches, next_start,
+         oldest_game_creation, matches_fetched, matches_skipped, completed, updated_at)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10, CURRENT_TIMESTAMP)
+        ON CONFLICT (puuid) DO UPDATE
+        SET regional = excluded.regional,
+            anchor_time = excluded.anchor_time,
+            cutoff_time = excluded.cutoff_time,
+            max_matches = excluded.max_matches,
+            next_start = excluded.next_start,
+            oldest_game_creation = excluded.oldest_game_creation,
+            matches_fetched = excluded.matches_fetched,
+            matches_skipped = excluded.matches_skipped,
+            completed = excluded.completed,
+            updated_at = CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(&checkpoint.puuid)
+    .bind(&checkpoint.regional)
+    .bind(checkpoint.anchor_time)
+    .bind(checkpoint.cutoff_time)
+    .bind(checkpoint.max_matches)
+    .bind(checkpoint.next_start)
+    .bind(checkpoint.oldest_game_creation)
+    .bind(checkpoint.matches_fetched)
+    .bind(checkpoint.matches_skipped)
+    .bind(checkpoint.completed)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Summaries of `match_ids` for `puuid`, in the same order.
+/// Matches that are not stored, or that `puuid` did not play in, are left out.
+pub async fn summarize_matches(
+    pool: &SqlitePool,
+    match_ids: &[String],
+    puuid: &str,
+    ddragon_version: &str,
+) -> Result<Vec<MatchSummary>> {
+    let rows = sqlx::query_as::<_, StoredMatch>(
+        r#"
+        SELECT "matchId", participants, "gameCreation", "gameDuration", "queueType"
+        FROM match_details
+        WHERE "matchId" IN (SELECT value FROM json_each($1))
+        "#
+    )
+    .bind(json_list(match_ids))
+    .fetch_all(pool)
+    .await?;
+    Ok(summarize_stored(&rows, match_ids, puuid, ddragon_version))
+}
+
+/// Full scoreboard of a stored match. Names come from `summoners`, falling back to
+/// the Riot ID stored with the participant.
+pub async fn get_match_details_from_db(
+    pool: &SqlitePool,
+    match_id: &str,
+    user_puuid: &str,
+    ddragon_version: &str,
+) -> Result<MatchDetails> {
+    let row = sqlx::query_as::<_, StoredMatch>(
+        r#"
+        SELECT "matchId", participants, "gameCreation", "gameDuration", "queueType"
+        FROM match_details
+        WHERE "matchId" = $1
+        "#
+    )
+    .bind(match_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(row) = row else {
+        return Err(DianaError::MatchNotFound(match_id.to_string()).into());
+    };
+    let names: HashMap<String, String> = sqlx::query_as::<_, (String, String, String)>(
+        r#"
+        SELECT puuid, "gameName", "tagLine"
+        FROM summoners
+        WHERE puuid IN (SELECT value FROM json_each($1))
+        "#
+    )
+    .bind(json_list(&row.participant_puuids()))
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(puuid, name, tag)| (puuid, format!("{}#{}", name, tag)))
+    .collect();
+
+    Ok(row.into_details(&names, user_puuid, ddragon_version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ME: &str = "me";
+    const ARAM: i32 = 450;
+    const HOWLING_ABYSS: i32 = 12;
+
+    /// Migrated in-memory database with the player stored.
+    async fn memory_pool() -> SqlitePool {
+        let options = SqliteConnectOptions::new().in_memory(true).foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await
+            .unwrap();
+        migrate(&pool).await.unwrap();
+        let summoner = NewSummoner {
+            puuid: ME,
+            game_name: "Me",
+            tag_line: "EUW",
+            region: "EUW",
+            match_region_prefix: "EUW1",
+            region_group: "europe",
+            tier: None,
+            rank: None,
+            lp: None,
+        };
+        upsert_summoner(&pool, summoner).await.unwrap();
+        pool
+    }
+
+    fn player(puuid: &str, team_id: i32, position: &str, champion: &str, win: bool) -> Value {
+        json!({
+            "puuid": puuid,
+            "teamId": team_id,
+            "teamPosition": position,
+            "championName": champion,
+            "kills": 3,
+            "deaths": 2,
+            "assists": 5,
+            "win": win,
+        })
+    }
+
+    async fn store_match(
+        pool: &SqlitePool,
+        match_id: &str,
+        game_creation: i64,
+        queue: i32,
+        duration: i32,
+        players: Vec<Value>,
+    ) {
+        let map = if queue == ARAM { HOWLING_ABYSS } else { SUMMONERS_RIFT };
+        let mut tx = pool.begin().await.unwrap();
+        let participants = Value::Array(players);
+        let teams = json!({ "queueId": queue });
+        let row = NewMatchDetails {
+            match_id,
+            entry_puuid: ME,
+            game_version: None,
+            game_creation: Some(game_creation),
+            game_start: None,
+            game_end: None,
+            game_duration: Some(duration),
+            game_mode: None,
+            game_type: None,
+            queue_type: Some(queue),
+            map_name: Some(map),
+            participants: &participants,
+            teams: &teams,
+        };
+        insert_match_details_tx(&mut tx, row).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn role_stats_read_stored_rift_games() {
+        let pool = memory_pool().await;
+        store_match(&pool, "EUW1_1", 1, RANKED_SOLO, 1800, vec![player(ME, 100, "TOP", "Garen", true)]).await;
+        store_match(&pool, "EUW1_2", 2, RANKED_SOLO, 1800, vec![player(ME, 100, "UTILITY", "Lulu", false)]).await;
+        // A remake and a game off the Rift.
+        store_match(&pool, "EUW1_3", 3, RANKED_SOLO, 200, vec![player(ME, 100, "JUNGLE", "Vi", false)]).await;
+        store_match(&pool, "EUW1_4", 4, ARAM, 1200, vec![player(ME, 100, "", "Sona", true)]).await;
+
+        let stats = get_role_stats(&pool, ME, QueueGroup::All).await.unwrap();
+        let games = |role: &str| stats.iter().find(|s| s.role == role).unwrap().games;
+        assert_eq!((games("TOP"), games("UTILITY"), games("JUNGLE")), (1, 1, 0));
+        assert_eq!(stats.iter().map(|s| s.games).sum::<i32>(), 2);
+
+        let flex = get_role_stats(&pool, ME, QueueGroup::RankedFlex).await.unwrap();
+        assert!(flex.iter().all(|s| s.games == 0));
+    }
+
+    #[tokio::test]
+    async fn a_match_ingested_twice_keeps_one_copy_of_its_frames() {
+        let pool = memory_pool().await;
+        let frame = |frame_index| TimelineFrame {
+            frame_index,
+            timestamp: Some(frame_index as i64 * 60_000),
+            participant_frames: json!({}),
+            events: json!([]),
+        };
+        for _ in 0..2 {
+            store_match(&pool, "EUW1_1", 1, RANKED_SOLO, 1800, vec![player(ME, 100, "TOP", "Garen", true)]).await;
+            let mid = sqlx::query_scalar::<Sqlite, i64>(r#"SELECT mid FROM match_details WHERE "matchId" = 'EUW1_1'"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            insert_timeline_frames(&pool, mid, ME, vec![frame(0), frame(1)]).await.unwrap();
+        }
+
+        let frames = sqlx::query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM match_timeline")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(frames, 2);
+    }
+
+    #[tokio::test]
+    async fn matchups_pair_the_same_position_on_the_other_team() {
+        let pool = memory_pool().await;
+        let lane = |me_win: bool, opponent: &str| {
+            vec![
+                player(ME, 100, "MIDDLE", "Ahri", me_win),
+                player("ally", 100, "TOP", "Darius", me_win),
+                player("top", 200, "TOP", "Garen", !me_win),
+                player("mid", 200, "MIDDLE", opponent, !me_win),
+            ]
+        };
+        store_match(&pool, "EUW1_1", 1, RANKED_SOLO, 1800, lane(true, "Zed")).await;
+        store_match(&pool, "EUW1_2", 2, RANKED_SOLO, 1800, lane(false, "Zed")).await;
+        store_match(&pool, "EUW1_3", 3, RANKED_SOLO, 1800, lane(true, "Yasuo")).await;
+        // A remake does not count towards the matchup.
+        store_match(&pool, "EUW1_4", 4, RANKED_SOLO, 200, lane(true, "Zed")).await;
+
+        let twice = get_matchup_stats(&pool, ME, QueueGroup::All, 2).await.unwrap();
+        let [zed] = twice.as_slice() else { panic!("expected one matchup, got {:?}", twice) };
+        assert_eq!(
+            (zed.champion_name.as_str(), zed.opponent_champion_name.as_str(), zed.role.as_str()),
+            ("Ahri", "Zed", "MIDDLE")
+        );
+        assert_eq!((zed.games, zed.wins, zed.winrate), (2, 1, 50.0));
+
+        let once = get_matchup_stats(&pool, ME, QueueGroup::All, 1).await.unwrap();
+        let opponents: Vec<&str> = once.iter().map(|m| m.opponent_champion_name.as_str()).collect();
+        assert_eq!(opponents, ["Zed", "Yasuo"]);
+    }
+
+    #[tokio::test]
+    async fn teammates_split_the_record_with_and_without_them() {
+        let pool = memory_pool().await;
+        let bob = |team_id, win, tag: &str| {
+            let mut bob = player("bob", team_id, "JUNGLE", "Vi", win);
+            bob["riotIdGameName"] = json!("Bob");
+            bob["riotIdTagline"] = json!(tag);
+            bob
+        };
+        let me = |win| player(ME, 100, "MIDDLE", "Ahri", win);
+        store_match(&pool, "EUW1_1", 1, RANKED_SOLO, 1800, vec![me(true), bob(100, true, "OLD")]).await;
+        store_match(&pool, "EUW1_2", 2, RANKED_SOLO, 1800, vec![me(true), bob(100, true, "NEW")]).await;
+        // Bob on the other team is not a teammate.
+        store_match(&pool, "EUW1_3", 3, RANKED_SOLO, 1800, vec![me(false), bob(200, true, "NEW")]).await;
+        store_match(&pool, "EUW1_4", 4, RANKED_SOLO, 1800, vec![me(false), player("cara", 100, "TOP", "Sett", false)]).await;
+        store_match(&pool, "EUW1_5", 5, RANKED_SOLO, 200, vec![me(false), bob(100, false, "NEW")]).await;
+
+        let duos = get_teammates(&pool, ME, QueueGroup::All, 2).await.unwrap();
+        let [bob] = duos.as_slice() else { panic!("expected one teammate, got {:?}", duos) };
+        assert_eq!(bob.puuid, "bob");
+        assert_eq!(bob.riot_id.as_deref(), Some("Bob#NEW"));
+        assert_eq!((bob.games, bob.wins), (2, 2));
+        // Without Bob: 2 games, both lost.
+        assert_eq!((bob.total_games - bob.games, bob.total_wins - bob.wins), (2, 0));
+
+        let everyone = get_teammates(&pool, ME, QueueGroup::All, 1).await.unwrap();
+        let puuids: Vec<&str> = everyone.iter().map(|t| t.puuid.as_str()).collect();
+        assert_eq!(puuids, ["bob", "cara"]);
+    }
+
+    #[tokio::test]
+    async fn queue_groups_narrow_stats_to_their_queues() {
+        assert_eq!(queue_filter(QueueGroup::All), None);
+        assert_eq!(queue_filter(QueueGroup::RankedFlex).as_deref(), Some("[440]"));
+
+        let pool = memory_pool().await;
+        let now = chrono::Utc::now().timestamp_millis();
+        for (i, queue) in [RANKED_SOLO, RANKED_SOLO, 440, ARAM, 1700].into_iter().enumerate() {
+            let match_id = format!("EUW1_{}", i);
+            store_match(&pool, &match_id, now - i as i64, queue, 1800, vec![player(ME, 100, "", "Lux", true)]).await;
+        }
+
+        for (queue, games) in [
+            (QueueGroup::RankedSolo, 2),
+            (QueueGroup::RankedFlex, 1),
+            (QueueGroup::Aram, 1),
+            (QueueGroup::Normal, 0),
+            (QueueGroup::All, 5),
+        ] {
+            let days = get_daily_activity(&pool, ME, queue).await.unwrap();
+            assert_eq!(days.len(), 30);
+            assert_eq!(days.iter().map(|d| d.games).sum::<i32>(), games, "{:?}", queue);
+        }
+    }
+}
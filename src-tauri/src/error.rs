@@ -0,0 +1,127 @@
+use std::fmt;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// Error returned by every Tauri command.
+///
+/// Serialized as `{ code, message, retry_after }` so the frontend can branch on a
+/// stable `code` instead of parsing messages.
+#[derive(Debug, Clone)]
+pub enum DianaError {
+    PlayerNotFound(String),
+    MatchNotFound(String),
+    InvalidRegion(String),
+    ProxyUnavailable(String),
+    RateLimited { retry_after: Option<u64> },
+    RiotKeyExpired,
+    Riot { status: u16, message: String },
+    Database(String),
+    Internal(String),
+}
+
+impl DianaError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DianaError::PlayerNotFound(_) => "player_not_found",
+            DianaError::MatchNotFound(_) => "match_not_found",
+            DianaError::InvalidRegion(_) => "invalid_region",
+            DianaError::ProxyUnavailable(_) => "proxy_unavailable",
+            DianaError::RateLimited { .. } => "rate_limited",
+            DianaError::RiotKeyExpired => "riot_key_expired",
+            DianaError::Riot { .. } => "riot_api",
+            DianaError::Database(_) => "database",
+            DianaError::Internal(_) => "internal",
+        }
+    }
+
+    /// Seconds the caller should wait before retrying, when known.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            DianaError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Maps a non-success Riot API status (as relayed by the proxy) to an error.
+    pub fn from_riot_status(status: u16, body: &str, retry_after: Option<u64>) -> Self {
+        match status {
+            401 | 403 => DianaError::RiotKeyExpired,
+            404 => DianaError::Riot { status, message: "Not found".to_string() },
+            429 => DianaError::RateLimited { retry_after },
+            _ => DianaError::Riot { status, message: body.to_string() },
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, DianaError::Riot { status: 404, .. })
+    }
+
+    /// Whether the same request may succeed later: rate limits, Riot outages and an
+    /// unreachable proxy or database.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            DianaError::RateLimited { .. }
+            | DianaError::ProxyUnavailable(_)
+            | DianaError::Database(_) => true,
+            DianaError::Riot { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DianaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DianaError::PlayerNotFound(who) => write!(f, "Player {} was not found", who),
+            DianaError::MatchNotFound(id) => write!(f, "Match {} was not found", id),
+            DianaError::InvalidRegion(region) => write!(f, "Unsupported region: {}", region),
+            DianaError::ProxyUnavailable(reason) => write!(
+                f,
+                "Proxy server is not available. Please start the diana-proxy server. ({})",
+                reason
+            ),
+            DianaError::RateLimited { retry_after: Some(secs) } => {
+                write!(f, "Riot API rate limit reached, retry in {}s", secs)
+            }
+            DianaError::RateLimited { retry_after: None } => {
+                write!(f, "Riot API rate limit reached, please retry shortly")
+            }
+            DianaError::RiotKeyExpired => {
+                write!(f, "The Riot API key was rejected, it has probably expired")
+            }
+            DianaError::Riot { status, message } => write!(f, "Riot API {}: {}", status, message),
+            DianaError::Database(message) => write!(f, "Database error: {}", message),
+            DianaError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DianaError {}
+
+impl Serialize for DianaError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DianaError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("retry_after", &self.retry_after())?;
+        s.end()
+    }
+}
+
+impl From<anyhow::Error> for DianaError {
+    fn from(err: anyhow::Error) -> Self {
+        if let Some(e) = err.downcast_ref::<DianaError>() {
+            return e.clone();
+        }
+        if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+            if e.is_connect() || e.is_timeout() {
+                return DianaError::ProxyUnavailable(e.to_string());
+            }
+        }
+        if let Some(e) = err.downcast_ref::<sqlx::Error>() {
+            return DianaError::Database(e.to_string());
+        }
+        DianaError::Internal(format!("{:#}", err))
+    }
+}
@@ -0,0 +1,178 @@
+use std::env;
+use std::future::Future;
+
+use anyhow::Result;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+
+use crate::{
+    error::DianaError,
+    models::DbMatchRow,
+    riot::RiotClient,
+    store::Store,
+    sync::{insert_match_with_options, store_missing_timeline},
+};
+
+/// Matches fetched at once when `DIANA_SYNC_CONCURRENCY` is not set.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// How many matches are fetched and stored at the same time.
+///
+/// Riot requests still go through the shared rate limiter, so raising this only helps
+/// while there is budget left; past that, tasks just wait for their slot.
+pub fn concurrency_from_env() -> usize {
+    env::var("DIANA_SYNC_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MatchOutcome {
+    Inserted { game_creation: i64 },
+    AlreadyStored,
+    /// `retryable` is false for matches that will fail the same way every time, like
+    /// ones Riot does not have.
+    Failed { error: String, retryable: bool },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct IngestOptions {
+    /// Leave timelines for the timeline backfill.
+    pub skip_timeline: bool,
+    pub concurrency: usize,
+}
+
+impl IngestOptions {
+    pub fn from_env(skip_timeline: bool) -> Self {
+        Self { skip_timeline, concurrency: concurrency_from_env() }
+    }
+}
+
+/// What happened to one match during an ingest.
+#[derive(Serialize, Clone, Debug)]
+pub struct MatchIngestResult {
+    pub match_id: String,
+    #[serde(flatten)]
+    pub outcome: MatchOutcome,
+}
+
+impl MatchIngestResult {
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, MatchOutcome::Failed { .. })
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.outcome, MatchOutcome::Failed { retryable: true, .. })
+    }
+}
+
+/// Keeps the IDs that are not stored yet, in their original order.
+pub async fn filter_missing(store: &dyn Store, match_ids: Vec<String>) -> Result<Vec<String>> {
+    let existing = store.matches_exist(&match_ids).await?;
+    Ok(match_ids.into_iter().filter(|id| !existing.contains(id)).collect())
+}
+
+async fn ingest_one(
+    client: &RiotClient,
+    store: &dyn Store,
+    regional: &str,
+    entry_puuid: &str,
+    match_id: &str,
+    skip_timeline: bool,
+) -> MatchIngestResult {
+    let outcome =
+        match insert_match_with_options(client, store, regional, entry_puuid, match_id, skip_timeline)
+            .await
+        {
+            Ok(Some(game_creation)) => MatchOutcome::Inserted { game_creation },
+            Ok(None) => MatchOutcome::AlreadyStored,
+            Err(e) => {
+                eprintln!("[INGEST] Failed to store match {}: {:#}", match_id, e);
+                failed(e)
+            }
+        };
+    MatchIngestResult { match_id: match_id.to_string(), outcome }
+}
+
+fn failed(e: anyhow::Error) -> MatchOutcome {
+    let error = format!("{:#}", e);
+    MatchOutcome::Failed { error, retryable: DianaError::from(e).is_transient() }
+}
+
+/// Fetches and stores `match_ids`, up to `options.concurrency` at a time.
+///
+/// A failing match does not stop the others. `on_result` is called as each match
+/// finishes (in completion order) and the returned results follow `match_ids` order.
+pub async fn ingest_matches<F>(
+    client: &RiotClient,
+    store: &dyn Store,
+    regional: &str,
+    entry_puuid: &str,
+    match_ids: &[String],
+    options: IngestOptions,
+    on_result: F,
+) -> Vec<MatchIngestResult>
+where
+    F: FnMut(&MatchIngestResult),
+{
+    let jobs = match_ids.iter().map(|match_id| {
+        ingest_one(client, store, regional, entry_puuid, match_id, options.skip_timeline)
+    });
+    run_bounded(jobs, options.concurrency, on_result).await
+}
+
+async fn ingest_timeline_one(client: &RiotClient, store: &dyn Store, row: &DbMatchRow) -> MatchIngestResult {
+    let outcome = match store_missing_timeline(client, store, row).await {
+        Ok(()) => MatchOutcome::Inserted { game_creation: row.gameCreation },
+        Err(e) => {
+            eprintln!("[INGEST] Failed to store timeline for {}: {:#}", row.matchId, e);
+            failed(e)
+        }
+    };
+    MatchIngestResult { match_id: row.matchId.clone(), outcome }
+}
+
+/// Fetches and stores the timelines of already stored matches, up to `concurrency` at a time.
+/// Works like [`ingest_matches`].
+pub async fn ingest_timelines<F>(
+    client: &RiotClient,
+    store: &dyn Store,
+    rows: &[DbMatchRow],
+    concurrency: usize,
+    on_result: F,
+) -> Vec<MatchIngestResult>
+where
+    F: FnMut(&MatchIngestResult),
+{
+    let jobs = rows.iter().map(|row| ingest_timeline_one(client, store, row));
+    run_bounded(jobs, concurrency, on_result).await
+}
+
+/// Polls `jobs` with at most `concurrency` running at once.
+async fn run_bounded<I, Fut, F>(jobs: I, concurrency: usize, mut on_result: F) -> Vec<MatchIngestResult>
+where
+    I: Iterator<Item = Fut>,
+    Fut: Future<Output = MatchIngestResult>,
+    F: FnMut(&MatchIngestResult),
+{
+    let mut results: Vec<(usize, MatchIngestResult)> = Vec::new();
+    let mut queue = jobs.enumerate();
+    let mut in_flight = FuturesUnordered::new();
+
+    loop {
+        while in_flight.len() < concurrency.max(1) {
+            let Some((idx, job)) = queue.next() else { break };
+            in_flight.push(async move { (idx, job.await) });
+        }
+
+        let Some((idx, result)) = in_flight.next().await else { break };
+        on_result(&result);
+        results.push((idx, result));
+    }
+
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -1,36 +1,61 @@
 // Prevent console window from appearing on Windows
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod app_state;
+mod backfill;
+mod error;
+mod ingest;
 mod models;
+mod queues;
 mod riot;
+mod riot_source;
+mod db;
 mod db_proxy;
+mod db_sqlite;
 mod match_summary;
+mod rank;
+mod rate_limit;
+mod region;
+mod roles;
+mod store;
 mod sync;
+mod sync_manager;
 
-use anyhow::Result;
-use models::{PlayerOverview, PlayerQuery, MatchQuery, MatchDetails, ActivityQuery, DailyActivityEntry};
+use app_state::AppState;
+use error::DianaError;
+use sync_manager::{SyncJob, SyncManager};
+use tauri::Manager;
+use models::{
+    PlayerOverview, PlayerQuery, MatchQuery, MatchDetails, ActivityQuery, DailyActivityEntry,
+    BackfillQuery, BackfillCheckpoint, MatchupStats, RoleStats, SchemaVersion, Teammate,
+};
+use queues::QueueGroup;
 
 #[tauri::command]
-async fn get_player_overview(query: PlayerQuery) -> Result<PlayerOverview, String> {
+async fn get_player_overview(
+    state: tauri::State<'_, AppState>,
+    query: PlayerQuery,
+    sync_manager: tauri::State<'_, SyncManager>,
+) -> Result<PlayerOverview, DianaError> {
     let call_id = uuid::Uuid::new_v4();
     eprintln!("[MAIN] get_player_overview START {call_id} for player: {}#{} region: {}", query.name, query.tag, query.region);
     eprintln!("[MAIN] If you see this log but no [SYNC] logs, there's an issue in sync.rs");
     let out = async {
-        let client = reqwest::Client::builder().user_agent("Diana/0.1.0").build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-        
-        let proxy_available = riot::check_proxy_connectivity(&client)
-            .await
-            .map_err(|e| format!("Failed to check proxy connectivity: {}", e))?;
-        
-        if !proxy_available {
-            return Err("Proxy server is not available. Please start the diana-proxy server.".to_string());
-        }
-        
-        let pool = db_proxy::init_pool().await.map_err(|e| e.to_string())?;
-        sync::sync_player_and_get_overview(&pool, &query.region, &query.name, &query.tag, "")
-            .await
-            .map_err(|e| e.to_string())
+        let platform = query.platform()?;
+        state.health.ensure_available()?;
+        let (client, store) = (&state.client, state.store.as_ref());
+
+        let player = sync::refresh_summoner(client, store, platform, &query.name, &query.tag).await?;
+
+        // Matches are fetched in the background, the UI gets what is stored right away
+        // and refetches once `sync://progress` reports the job as done.
+        let puuid = player.account.puuid.clone();
+        sync_manager.enqueue(SyncJob { puuid: puuid.clone(), platform });
+
+        let ddragon_version = state.ddragon.version(client).await?;
+        let mut overview = sync::get_cached_overview(store, platform, &player, query.queue, &ddragon_version).await?;
+        overview.syncing = sync_manager.is_syncing(&puuid);
+        Ok(overview)
     }
     .await;
     match &out {
@@ -51,41 +76,25 @@ async fn get_player_overview(query: PlayerQuery) -> Result<PlayerOverview, Strin
 }
 
 #[tauri::command]
-async fn get_match_details(query: MatchQuery) -> Result<MatchDetails, String> {
+async fn get_match_details(
+    state: tauri::State<'_, AppState>,
+    query: MatchQuery,
+) -> Result<MatchDetails, DianaError> {
     let call_id = uuid::Uuid::new_v4();
     eprintln!("get_match_details START {call_id} match_id={}", query.match_id);
     let out = async {
-        let client = reqwest::Client::builder().user_agent("Diana/0.1.0").build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let platform = region::Platform::from_match_id(&query.match_id)?;
+        state.health.ensure_available()?;
+        let (client, store) = (&state.client, state.store.as_ref());
+        let ddragon_version = state.ddragon.version(client).await?;
         
-        let proxy_available = riot::check_proxy_connectivity(&client)
-            .await
-            .map_err(|e| format!("Failed to check proxy connectivity: {}", e))?;
-        
-        if !proxy_available {
-            return Err("Proxy server is not available. Please start the diana-proxy server.".to_string());
-        }
+        let regional = platform.match_route().as_str();
+        sync::ensure_match_stored(client, store, regional, &query.user_puuid, &query.match_id).await?;
         
-        let pool = db_proxy::init_pool().await.map_err(|e| e.to_string())?;
-        let ddragon_version = riot::get_latest_ddragon_version(&client)
-            .await
-            .map_err(|e| e.to_string())?;
-        
-        let user_region = "EUW";
-        let (_, regional) = riot::map_region(user_region)
-            .ok_or_else(|| "Invalid user region".to_string())?;
-        
-        db_proxy::get_match_details_from_db(
-            &pool,
-            &query.match_id,
-            &query.user_puuid,
-            &ddragon_version,
-            &client,
-            "",
-            regional,
-        )
-        .await
-        .map_err(|e| e.to_string())
+        let details = store
+            .get_match_details(&query.match_id, &query.user_puuid, &ddragon_version)
+            .await?;
+        Ok(details)
     }
     .await;
     eprintln!("get_match_details END {call_id} ok={}", out.is_ok());
@@ -93,20 +102,170 @@ async fn get_match_details(query: MatchQuery) -> Result<MatchDetails, String> {
 }
 
 #[tauri::command]
-async fn get_daily_activity(query: ActivityQuery) -> Result<Vec<DailyActivityEntry>, String> {
+async fn get_daily_activity(
+    state: tauri::State<'_, AppState>,
+    query: ActivityQuery,
+) -> Result<Vec<DailyActivityEntry>, DianaError> {
     let call_id = uuid::Uuid::new_v4();
     eprintln!("get_daily_activity START {call_id} user_puuid={}", query.user_puuid);
     let out = async {
-        let pool = db_proxy::init_pool().await.map_err(|e| e.to_string())?;
-        db_proxy::get_daily_activity(&pool, &query.user_puuid)
-            .await
-            .map_err(|e| e.to_string())
+        state.health.ensure_store_available(state.store.as_ref())?;
+        let activity = state.store.get_daily_activity(&query.user_puuid, query.queue).await?;
+        Ok(activity)
     }
     .await;
     eprintln!("get_daily_activity END {call_id} ok={}", out.is_ok());
     out
 }
 
+#[tauri::command]
+async fn get_role_stats(
+    state: tauri::State<'_, AppState>,
+    puuid: String,
+    queue: Option<QueueGroup>,
+) -> Result<Vec<RoleStats>, DianaError> {
+    let call_id = uuid::Uuid::new_v4();
+    eprintln!("get_role_stats START {call_id} puuid={}", puuid);
+    let out = async {
+        state.health.ensure_store_available(state.store.as_ref())?;
+        let stats = state.store.get_role_stats(&puuid, queue.unwrap_or_default()).await?;
+        Ok(stats)
+    }
+    .await;
+    eprintln!("get_role_stats END {call_id} ok={}", out.is_ok());
+    out
+}
+
+#[tauri::command]
+async fn get_matchup_stats(
+    state: tauri::State<'_, AppState>,
+    puuid: String,
+    queue: Option<QueueGroup>,
+    min_games: Option<i32>,
+) -> Result<Vec<MatchupStats>, DianaError> {
+    let call_id = uuid::Uuid::new_v4();
+    eprintln!("get_matchup_stats START {call_id} puuid={}", puuid);
+    let out = async {
+        state.health.ensure_store_available(state.store.as_ref())?;
+        let min_games = min_games.unwrap_or(db::DEFAULT_MATCHUP_MIN_GAMES).max(1);
+        let matchups = state
+            .store
+            .get_matchup_stats(&puuid, queue.unwrap_or_default(), min_games)
+            .await?;
+        Ok(matchups)
+    }
+    .await;
+    eprintln!("get_matchup_stats END {call_id} ok={}", out.is_ok());
+    out
+}
+
+#[tauri::command]
+async fn get_teammates(
+    state: tauri::State<'_, AppState>,
+    puuid: String,
+    queue: Option<QueueGroup>,
+    min_games: Option<i32>,
+) -> Result<Vec<Teammate>, DianaError> {
+    let call_id = uuid::Uuid::new_v4();
+    eprintln!("get_teammates START {call_id} puuid={}", puuid);
+    let out = async {
+        // Names Riot can't resolve fall back to placeholders, only the store has to answer.
+        state.health.ensure_store_available(state.store.as_ref())?;
+        let min_games = min_games.unwrap_or(db::DEFAULT_TEAMMATE_MIN_GAMES).max(1);
+        let teammates = sync::get_teammates(
+            &state.client,
+            state.store.as_ref(),
+            &puuid,
+            queue.unwrap_or_default(),
+            min_games,
+        )
+        .await?;
+        Ok(teammates)
+    }
+    .await;
+    eprintln!("get_teammates END {call_id} ok={}", out.is_ok());
+    out
+}
+
+#[tauri::command]
+async fn backfill_history(
+    state: tauri::State<'_, AppState>,
+    query: BackfillQuery,
+) -> Result<BackfillCheckpoint, DianaError> {
+    let call_id = uuid::Uuid::new_v4();
+    eprintln!("backfill_history START {call_id} puuid={}", query.puuid);
+    let out = async {
+        let platform: region::Platform = query.region.parse()?;
+        let limits = backfill::BackfillLimits {
+            cutoff_time: query.since,
+            max_matches: query.max_matches,
+        };
+        let checkpoint = backfill::run_backfill(
+            &state.client,
+            state.store.as_ref(),
+            platform.match_route().as_str(),
+            &query.puuid,
+            limits,
+        )
+        .await?;
+        Ok(checkpoint)
+    }
+    .await;
+    eprintln!("backfill_history END {call_id} ok={}", out.is_ok());
+    out
+}
+
+#[tauri::command]
+async fn backfill_timelines(
+    state: tauri::State<'_, AppState>,
+    puuid: String,
+) -> Result<Vec<ingest::MatchIngestResult>, DianaError> {
+    let call_id = uuid::Uuid::new_v4();
+    eprintln!("backfill_timelines START {call_id} puuid={}", puuid);
+    let out = async {
+        let results = backfill::backfill_timelines(
+            &state.client,
+            state.store.as_ref(),
+            &puuid,
+            backfill::DEFAULT_TIMELINE_BACKFILL_LIMIT,
+        )
+        .await?;
+        Ok(results)
+    }
+    .await;
+    eprintln!("backfill_timelines END {call_id} ok={}", out.is_ok());
+    out
+}
+
+#[tauri::command]
+fn get_queue_catalog() -> Vec<queues::QueueInfo> {
+    queues::QUEUES.to_vec()
+}
+
+#[tauri::command]
+async fn get_schema_version(
+    state: tauri::State<'_, AppState>,
+) -> Result<SchemaVersion, DianaError> {
+    Ok(state.store.schema_version().await?)
+}
+
+/// Runs the store's migrations before any command can touch the database.
+/// A failure is logged and left for the commands to report.
+async fn migrate_store(store: &dyn store::Store) {
+    let result = async {
+        store.migrate().await?;
+        store.schema_version().await
+    }
+    .await;
+    match result {
+        Ok(schema) => eprintln!(
+            "[MAIN] {} schema at version {:?} (latest {})",
+            schema.store, schema.version, schema.latest
+        ),
+        Err(e) => eprintln!("[MAIN] ❌ Migrations failed: {:#}", e),
+    }
+}
+
 pub fn main() {
     // Load environment variables from .env file if present (for development)
     dotenvy::dotenv().ok();
@@ -116,7 +275,14 @@ pub fn main() {
         .try_init();
 
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![get_player_overview, get_match_details, get_daily_activity])
+        .setup(|app| {
+            let state = tauri::async_runtime::block_on(AppState::init(app.handle()))?;
+            tauri::async_runtime::block_on(migrate_store(state.store.as_ref()));
+            app.manage(state);
+            app.manage(SyncManager::start(app.handle().clone()));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![get_player_overview, get_match_details, get_daily_activity, get_role_stats, get_matchup_stats, get_teammates, backfill_history, backfill_timelines, get_queue_catalog, get_schema_version])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
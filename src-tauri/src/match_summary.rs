@@ -1,4 +1,7 @@
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::models::MatchParticipantDetail;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MatchSummary {
@@ -58,3 +61,145 @@ pub struct MatchSummary {
     pub ddragon_version: String,
 }
 
+
+/// Reads an integer field of a stored participant, 0 when missing.
+fn int(p: &Value, key: &str) -> i32 {
+    p.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as i32
+}
+
+fn round2(x: f64) -> f32 {
+    ((x * 100.0).round() / 100.0) as f32
+}
+
+/// Per-minute rates and KDA shared by the summary and the details view.
+struct Derived {
+    cs: i32,
+    kda: f32,
+    gpm: f32,
+    cs_per_min: f32,
+    vision_per_min: f32,
+}
+
+fn derive(p: &Value, game_duration_s: i64) -> Derived {
+    let (kills, deaths, assists) = (int(p, "kills"), int(p, "deaths"), int(p, "assists"));
+    let cs = int(p, "totalMinionsKilled") + int(p, "neutralMinionsKilled");
+    let kda = if deaths == 0 {
+        (kills + assists) as f64
+    } else {
+        (kills + assists) as f64 / deaths as f64
+    };
+    let minutes = game_duration_s as f64 / 60.0;
+    let per_min = |x: i32| if minutes > 0.0 { round2(x as f64 / minutes) } else { 0.0 };
+    Derived {
+        cs,
+        kda: round2(kda),
+        gpm: per_min(int(p, "goldEarned")),
+        cs_per_min: per_min(cs),
+        vision_per_min: per_min(int(p, "visionScore")),
+    }
+}
+
+pub fn champion_icon_url(ddragon_version: &str, champion_name: &str) -> String {
+    format!(
+        "https://ddragon.leagueoflegends.com/cdn/{}/img/champion/{}.png",
+        ddragon_version, champion_name
+    )
+}
+
+fn items(p: &Value) -> [i32; 6] {
+    ["item0", "item1", "item2", "item3", "item4", "item5"].map(|key| int(p, key))
+}
+
+fn kill_participation(p: &Value) -> f32 {
+    p.get("challenges")
+        .and_then(|c| c.get("killParticipation"))
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0) as f32
+}
+
+/// Builds the summary of one player's game from their entry in `match_details.participants`.
+/// Same output as the proxy's `/db/match-summaries` route.
+pub fn summarize_participant(
+    match_id: &str,
+    queue_id: i32,
+    game_creation_ms: i64,
+    game_duration_s: i64,
+    p: &Value,
+    ddragon_version: &str,
+) -> MatchSummary {
+    let d = derive(p, game_duration_s);
+    let champion_name = p.get("championName").and_then(|v| v.as_str()).unwrap_or_default();
+    MatchSummary {
+        match_id: match_id.to_string(),
+        queue_id,
+        game_creation_ms,
+        game_duration_s,
+        win: p.get("win").and_then(|v| v.as_bool()).unwrap_or(false),
+        champion_name: champion_name.to_string(),
+        champion_icon_url: champion_icon_url(ddragon_version, champion_name),
+        kills: int(p, "kills"),
+        deaths: int(p, "deaths"),
+        assists: int(p, "assists"),
+        cs: d.cs,
+        kda: d.kda,
+        role: p.get("teamPosition").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+        gold_earned: int(p, "goldEarned"),
+        gpm: d.gpm,
+        cs_per_min: d.cs_per_min,
+        vision_per_min: d.vision_per_min,
+        items: items(p),
+        trinket: int(p, "item6"),
+        damage_dealt: int(p, "totalDamageDealtToChampions"),
+        damage_taken: int(p, "totalDamageTaken"),
+        vision_score: int(p, "visionScore"),
+        kill_participation: kill_participation(p),
+        turret_takedowns: int(p, "turretTakedowns"),
+        dragon_kills: int(p, "dragonKills"),
+        baron_kills: int(p, "baronKills"),
+        ddragon_version: ddragon_version.to_string(),
+    }
+}
+
+/// Builds one row of the match details table. Same output as the proxy's
+/// `/db/match-details` route.
+pub fn participant_detail(
+    p: &Value,
+    summoner_name: Option<String>,
+    game_duration_s: i64,
+    ddragon_version: &str,
+) -> MatchParticipantDetail {
+    let d = derive(p, game_duration_s);
+    let str_field = |key: &str| p.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+    let summoner_name = summoner_name
+        .or_else(|| Some(str_field("riotIdGameName")).filter(|s| !s.is_empty()).map(String::from))
+        .or_else(|| Some(str_field("summonerName")).filter(|s| !s.is_empty()).map(String::from))
+        .unwrap_or_else(|| "Human Bot".to_string());
+
+    MatchParticipantDetail {
+        puuid: str_field("puuid").to_string(),
+        summoner_name,
+        champion_name: str_field("championName").to_string(),
+        champion_icon_url: champion_icon_url(ddragon_version, str_field("championName")),
+        kills: int(p, "kills"),
+        deaths: int(p, "deaths"),
+        assists: int(p, "assists"),
+        cs: d.cs,
+        kda: d.kda,
+        role: str_field("teamPosition").to_string(),
+        gold_earned: int(p, "goldEarned"),
+        gpm: d.gpm,
+        cs_per_min: d.cs_per_min,
+        vision_per_min: d.vision_per_min,
+        items: items(p),
+        trinket: int(p, "item6"),
+        damage_dealt: int(p, "totalDamageDealtToChampions"),
+        damage_taken: int(p, "totalDamageTaken"),
+        vision_score: int(p, "visionScore"),
+        kill_participation: kill_participation(p),
+        turret_takedowns: int(p, "turretTakedowns"),
+        dragon_kills: int(p, "dragonKills"),
+        baron_kills: int(p, "baronKills"),
+        win: p.get("win").and_then(|v| v.as_bool()).unwrap_or(false),
+        team: if int(p, "teamId") == 100 { "Blue" } else { "Red" }.to_string(),
+    }
+}
@@ -1,11 +1,28 @@
+// Fields of Riot's DTOs and of the stored rows keep the camelCase names they have in
+// the JSON and in the database columns.
+#![allow(non_snake_case)]
+
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::error::DianaError;
+use crate::queues::QueueGroup;
+use crate::region::Platform;
+
 #[derive(Deserialize, Debug)]
 pub struct PlayerQuery {
     pub name: String,
     pub tag: String,
     pub region: String,
+    /// Queues the stats and top champions are computed over.
+    #[serde(default)]
+    pub queue: QueueGroup,
+}
+
+impl PlayerQuery {
+    pub fn platform(&self) -> Result<Platform, DianaError> {
+        self.region.parse()
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,6 +34,19 @@ pub struct MatchQuery {
 #[derive(Deserialize, Debug)]
 pub struct ActivityQuery {
     pub user_puuid: String,
+    #[serde(default)]
+    pub queue: QueueGroup,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BackfillQuery {
+    pub puuid: String,
+    pub region: String,
+    /// Oldest game to fetch, in epoch seconds.
+    #[serde(default)]
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub max_matches: Option<i32>,
 }
 
 
@@ -42,9 +72,6 @@ pub struct AccountDto {
 
 #[derive(Deserialize, Debug)]
 pub struct SummonerDto {
-    #[serde(default)]
-    pub id: String,
-    pub puuid: String,
     #[serde(default)]
     pub name: String,
     #[serde(default)]
@@ -71,6 +98,8 @@ pub struct MatchInfo {
     pub gameCreation: i64,
     pub gameDuration: i64,
     pub queueId: i32,
+    #[serde(default)]
+    pub mapId: Option<i32>,
     pub participants: Vec<ParticipantDto>,
 }
 
@@ -162,6 +191,16 @@ pub struct DbMatchRow {
     pub gameCreation: i64,
 }
 
+/// One frame of a match timeline, as stored in `match_timeline`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineFrame {
+    pub frame_index: i32,
+    pub timestamp: Option<i64>,
+    pub participant_frames: serde_json::Value,
+    pub events: serde_json::Value,
+}
+
 // Separate struct for JSON deserialization that can handle string/numeric IDs
 #[derive(Debug, serde::Deserialize)]
 pub struct DbMatchRowJson {
@@ -201,14 +240,88 @@ where
     }
 }
 
+/// Where a history backfill for one player stopped.
+///
+/// Paging uses a fixed `anchor_time` (endTime filter, epoch seconds) so offsets stay
+/// stable while the player keeps playing new games.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillCheckpoint {
+    pub puuid: String,
+    pub regional: String,
+    pub anchor_time: i64,
+    pub cutoff_time: Option<i64>,
+    pub max_matches: Option<i32>,
+    pub next_start: i32,
+    pub oldest_game_creation: Option<i64>,
+    pub matches_fetched: i32,
+    /// Matches moved past because they fail the same way every time, like ones Riot
+    /// does not have.
+    #[serde(default)]
+    pub matches_skipped: i32,
+    pub completed: bool,
+}
+
 #[derive(Serialize, Debug)]
 pub struct PlayerStats {
     pub winrate: f32,
     pub games: i32,
+    /// Current streak, positive for wins and negative for losses.
     pub streak: i32,
+    pub longest_win_streak: i32,
+    pub longest_loss_streak: i32,
     pub kda: f32,
 }
 
+/// Games shorter than this, in seconds, are remakes.
+pub const REMAKE_MAX_SECONDS: i32 = 300;
+
+/// One game of the player, as read for streaks.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct GameResult {
+    pub win: bool,
+    /// Epoch ms.
+    pub game_creation: i64,
+    /// Seconds.
+    pub game_duration: i32,
+}
+
+/// Win and loss streaks. The current one runs across seasons, the longest ones are
+/// over the season.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streaks {
+    /// Positive for wins, negative for losses.
+    pub current: i32,
+    pub longest_win: i32,
+    pub longest_loss: i32,
+}
+
+impl Streaks {
+    /// Streaks of `games`, oldest first. Remakes are skipped so they don't break a streak,
+    /// and only games from `season_start` (epoch ms) on count towards the longest ones.
+    pub fn from_results(games: &[GameResult], season_start: i64) -> Self {
+        fn extend(streak: i32, win: bool) -> i32 {
+            match (win, streak) {
+                (true, n) if n > 0 => n + 1,
+                (true, _) => 1,
+                (false, n) if n < 0 => n - 1,
+                (false, _) => -1,
+            }
+        }
+
+        let mut streaks = Self::default();
+        let mut season = 0;
+        for game in games.iter().filter(|g| g.game_duration >= REMAKE_MAX_SECONDS) {
+            streaks.current = extend(streaks.current, game.win);
+            if game.game_creation >= season_start {
+                season = extend(season, game.win);
+                streaks.longest_win = streaks.longest_win.max(season);
+                streaks.longest_loss = streaks.longest_loss.max(-season);
+            }
+        }
+        streaks
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TopChamp {
     pub champion_name: String,
@@ -219,6 +332,15 @@ pub struct TopChamp {
     pub kda: f32,
 }
 
+/// Solo queue rank as fetched from Riot at `taken_at` (epoch ms).
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct RankSnapshot {
+    pub tier: String,
+    pub division: String,
+    pub lp: i32,
+    pub taken_at: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RankStep {
     pub match_id: String,
@@ -234,6 +356,65 @@ pub struct RankStep {
     pub exact: bool,
 }
 
+/// How a player does in one role.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RoleStats {
+    pub role: String,
+    pub games: i32,
+    pub wins: i32,
+    /// Percentage, 0 to 100.
+    pub winrate: f32,
+    pub kda: f32,
+    pub cs_per_min: f32,
+    pub gold_per_min: f32,
+    pub vision_per_min: f32,
+    /// Share of the team's kills the player took part in, 0 to 100 like `winrate`.
+    pub kill_participation: f32,
+    /// Games counted here whose `teamPosition` was empty and got inferred.
+    pub inferred_games: i32,
+}
+
+/// The player's record on one champion against one lane opponent's champion, same
+/// `teamPosition` on the other team.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug)]
+pub struct MatchupStats {
+    pub champion_name: String,
+    pub opponent_champion_name: String,
+    pub role: String,
+    pub games: i32,
+    pub wins: i32,
+    pub winrate: f32,
+    /// Player's KDA minus the opponent's, averaged over the games.
+    pub avg_kda_diff: f32,
+    /// Player's gold minus the opponent's, averaged over the games.
+    pub avg_gold_diff: f32,
+}
+
+/// A player seen on the same team as the player, with the player's totals over the
+/// same games so the record without them can be worked out.
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug)]
+pub struct DbTeammate {
+    pub puuid: String,
+    pub games: i32,
+    pub wins: i32,
+    pub total_games: i32,
+    pub total_wins: i32,
+    /// Riot ID in the last game together, if the match data has one.
+    pub riot_id: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Teammate {
+    pub puuid: String,
+    /// Riot ID, `Player <puuid prefix>` when it could not be resolved.
+    pub name: String,
+    pub games_together: i32,
+    pub wins_together: i32,
+    pub winrate_together: f32,
+    pub games_without: i32,
+    pub winrate_without: f32,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MatchParticipantDetail {
     pub puuid: String,
@@ -274,6 +455,17 @@ pub struct MatchDetails {
     pub ddragon_version: String,
 }
 
+/// Schema migration state of the active store.
+#[derive(Serialize, Debug)]
+pub struct SchemaVersion {
+    /// `proxy`, `postgres` or `sqlite`.
+    pub store: String,
+    /// Newest migration applied, `None` when the database was never migrated.
+    pub version: Option<i64>,
+    /// Newest migration this build ships.
+    pub latest: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DailyActivityEntry {
     pub date: String,
@@ -287,4 +479,59 @@ pub struct PlayerOverview {
     pub stats: PlayerStats,
     pub top_champs: Vec<TopChamp>,
     pub ranked_progress: Vec<RankStep>,
+    /// Queues `stats` and `top_champs` cover.
+    pub queue: QueueGroup,
+    /// True while a background sync for this player is queued or running.
+    pub syncing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn games(results: &[(bool, i64, i32)]) -> Vec<GameResult> {
+        results
+            .iter()
+            .map(|&(win, game_creation, game_duration)| GameResult { win, game_creation, game_duration })
+            .collect()
+    }
+
+    #[test]
+    fn streaks_follow_wins_and_losses() {
+        let games = games(&[
+            (true, 1, 1800),
+            (true, 2, 1800),
+            (true, 3, 1800),
+            (false, 4, 1800),
+            (false, 5, 1800),
+        ]);
+        let streaks = Streaks::from_results(&games, 0);
+        assert_eq!(streaks, Streaks { current: -2, longest_win: 3, longest_loss: 2 });
+    }
+
+    #[test]
+    fn a_remake_does_not_break_a_streak() {
+        let games = games(&[(true, 1, 1800), (false, 2, 200), (true, 3, 1800), (true, 4, 1800)]);
+        let streaks = Streaks::from_results(&games, 0);
+        assert_eq!(streaks, Streaks { current: 3, longest_win: 3, longest_loss: 0 });
+    }
+
+    #[test]
+    fn only_the_longest_streaks_stop_at_the_season_start() {
+        let games = games(&[
+            (false, 1, 1800),
+            (false, 2, 1800),
+            (false, 3, 1800),
+            (true, 4, 1800),
+            (true, 5, 1800),
+            (true, 6, 1800),
+        ]);
+        // The season starts with the third loss.
+        let streaks = Streaks::from_results(&games, 3);
+        assert_eq!(streaks, Streaks { current: 3, longest_win: 3, longest_loss: 1 });
+
+        // A streak carried over from last season still counts as current.
+        let streaks = Streaks::from_results(&games[..3], 3);
+        assert_eq!(streaks, Streaks { current: -3, longest_win: 0, longest_loss: 1 });
+    }
 }
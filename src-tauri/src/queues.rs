@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// Ranked Solo/Duo, the queue rank, LP and rank snapshots are about.
+pub const RANKED_SOLO: i32 = 420;
+
+/// Queues the stats can be narrowed to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueGroup {
+    #[default]
+    RankedSolo,
+    RankedFlex,
+    Normal,
+    Aram,
+    Arena,
+    /// Every queue, including the ones outside the other groups.
+    All,
+}
+
+impl QueueGroup {
+    /// Key the group is cached under in `player_summary`, same as its serde name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueueGroup::RankedSolo => "ranked_solo",
+            QueueGroup::RankedFlex => "ranked_flex",
+            QueueGroup::Normal => "normal",
+            QueueGroup::Aram => "aram",
+            QueueGroup::Arena => "arena",
+            QueueGroup::All => "all",
+        }
+    }
+
+    /// Queue IDs in the group, `None` for [`QueueGroup::All`].
+    pub fn queue_ids(self) -> Option<Vec<i32>> {
+        if self == QueueGroup::All {
+            return None;
+        }
+        Some(QUEUES.iter().filter(|q| q.group == Some(self)).map(|q| q.id).collect())
+    }
+}
+
+/// A queue from Riot's `queueId`.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct QueueInfo {
+    pub id: i32,
+    pub name: &'static str,
+    /// `None` for queues only counted under [`QueueGroup::All`], like bots or rotating modes.
+    pub group: Option<QueueGroup>,
+}
+
+const fn queue(id: i32, name: &'static str, group: Option<QueueGroup>) -> QueueInfo {
+    QueueInfo { id, name, group }
+}
+
+/// Queues of the current match history, for the queue picker and the groups.
+pub const QUEUES: &[QueueInfo] = &[
+    queue(400, "Draft Pick", Some(QueueGroup::Normal)),
+    queue(420, "Ranked Solo/Duo", Some(QueueGroup::RankedSolo)),
+    queue(430, "Blind Pick", Some(QueueGroup::Normal)),
+    queue(440, "Ranked Flex", Some(QueueGroup::RankedFlex)),
+    queue(450, "ARAM", Some(QueueGroup::Aram)),
+    queue(480, "Swiftplay", Some(QueueGroup::Normal)),
+    queue(490, "Quickplay", Some(QueueGroup::Normal)),
+    queue(700, "Clash", None),
+    queue(720, "ARAM Clash", Some(QueueGroup::Aram)),
+    queue(830, "Co-op vs. AI Intro", None),
+    queue(840, "Co-op vs. AI Beginner", None),
+    queue(850, "Co-op vs. AI Intermediate", None),
+    queue(870, "Co-op vs. AI Intro", None),
+    queue(880, "Co-op vs. AI Beginner", None),
+    queue(890, "Co-op vs. AI Intermediate", None),
+    queue(900, "ARURF", None),
+    queue(1020, "One for All", None),
+    queue(1300, "Nexus Blitz", None),
+    queue(1700, "Arena", Some(QueueGroup::Arena)),
+    queue(1710, "Arena", Some(QueueGroup::Arena)),
+    queue(1900, "URF", None),
+    queue(2400, "ARAM: Mayhem", Some(QueueGroup::Aram)),
+];
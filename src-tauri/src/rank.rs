@@ -1,32 +1,169 @@
+use std::str::FromStr;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Tier { IRON, BRONZE, SILVER, GOLD, PLATINUM, EMERALD, DIAMOND, MASTER, GRANDMASTER, CHALLENGER }
+pub enum Tier { Iron, Bronze, Silver, Gold, Platinum, Emerald, Diamond, Master, Grandmaster, Challenger }
 
-impl Tier {
-    pub fn from_str(s: &str) -> Option<Self> {
+impl FromStr for Tier {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Tier::*;
         match s.to_ascii_uppercase().as_str() {
-            "IRON" => Some(IRON), "BRONZE" => Some(BRONZE), "SILVER" => Some(SILVER),
-            "GOLD" => Some(GOLD), "PLATINUM" => Some(PLATINUM), "EMERALD" => Some(EMERALD),
-            "DIAMOND" => Some(DIAMOND), "MASTER" => Some(MASTER),
-            "GRANDMASTER" => Some(GRANDMASTER), "CHALLENGER" => Some(CHALLENGER),
-            _ => None
+            "IRON" => Ok(Iron), "BRONZE" => Ok(Bronze), "SILVER" => Ok(Silver),
+            "GOLD" => Ok(Gold), "PLATINUM" => Ok(Platinum), "EMERALD" => Ok(Emerald),
+            "DIAMOND" => Ok(Diamond), "MASTER" => Ok(Master),
+            "GRANDMASTER" => Ok(Grandmaster), "CHALLENGER" => Ok(Challenger),
+            _ => Err(())
         }
     }
-    pub fn to_str(&self) -> &'static str { use Tier::*; match self {
-        IRON=>"IRON", BRONZE=>"BRONZE", SILVER=>"SILVER", GOLD=>"GOLD", PLATINUM=>"PLATINUM",
-        EMERALD=>"EMERALD", DIAMOND=>"DIAMOND", MASTER=>"MASTER", GRANDMASTER=>"GRANDMASTER", CHALLENGER=>"CHALLENGER"
+}
+
+impl Tier {
+    pub fn as_str(self) -> &'static str { use Tier::*; match self {
+        Iron=>"IRON", Bronze=>"BRONZE", Silver=>"SILVER", Gold=>"GOLD", Platinum=>"PLATINUM",
+        Emerald=>"EMERALD", Diamond=>"DIAMOND", Master=>"MASTER", Grandmaster=>"GRANDMASTER", Challenger=>"CHALLENGER"
     }}
 }
 
-pub fn tier_order(t: Tier) -> i32 {
+pub const fn tier_order(t: Tier) -> i32 {
     use Tier::*;
-    match t { IRON=>0, BRONZE=>1, SILVER=>2, GOLD=>3, PLATINUM=>4, EMERALD=>5, DIAMOND=>6, MASTER=>7, GRANDMASTER=>8, CHALLENGER=>9 }
+    match t { Iron=>0, Bronze=>1, Silver=>2, Gold=>3, Platinum=>4, Emerald=>5, Diamond=>6, Master=>7, Grandmaster=>8, Challenger=>9 }
 }
 pub fn next_tier(t: Tier) -> Tier { use Tier::*; match t {
-    IRON=>BRONZE, BRONZE=>SILVER, SILVER=>GOLD, GOLD=>PLATINUM, PLATINUM=>EMERALD,
-    EMERALD=>DIAMOND, DIAMOND=>MASTER, MASTER=>GRANDMASTER, GRANDMASTER=>CHALLENGER, CHALLENGER=>CHALLENGER
+    Iron=>Bronze, Bronze=>Silver, Silver=>Gold, Gold=>Platinum, Platinum=>Emerald,
+    Emerald=>Diamond, Diamond=>Master, Master=>Grandmaster, Grandmaster=>Challenger, Challenger=>Challenger
 }}
-pub fn prev_tier(t: Tier) -> Tier { use Tier::*; match t {
-    BRONZE=>IRON, SILVER=>BRONZE, GOLD=>SILVER, PLATINUM=>GOLD, EMERALD=>PLATINUM,
-    DIAMOND=>EMERALD, MASTER=>DIAMOND, GRANDMASTER=>MASTER, CHALLENGER=>GRANDMASTER, IRON=>IRON
-}}
\ No newline at end of file
+
+/// Divisions from lowest to highest. Master and above have none, Riot reports them as `I`.
+const DIVISIONS: [&str; 4] = ["IV", "III", "II", "I"];
+
+/// Ladder points between the floors of two divisions, and of two tiers.
+const DIVISION_SIZE: i32 = 100;
+const TIER_SIZE: i32 = DIVISIONS.len() as i32 * DIVISION_SIZE;
+
+/// Ladder value of Master 0 LP. Grandmaster and Challenger are Master players above an
+/// LP cutoff, so all three share the ladder from here up.
+const APEX_FLOOR: i32 = tier_floor(Tier::Master);
+
+const fn tier_floor(t: Tier) -> i32 { tier_order(t) * TIER_SIZE }
+
+pub fn has_divisions(t: Tier) -> bool { tier_order(t) < tier_order(Tier::Master) }
+
+/// A rank as the client shows it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rank {
+    pub tier: Tier,
+    pub division: &'static str,
+    pub lp: i32,
+}
+
+impl Rank {
+    /// `None` for an unknown tier, or an unknown division below Master.
+    pub fn parse(tier: &str, division: &str, lp: i32) -> Option<Self> {
+        let tier: Tier = tier.parse().ok()?;
+        let division = if has_divisions(tier) {
+            DIVISIONS.iter().copied().find(|d| d.eq_ignore_ascii_case(division))?
+        } else {
+            "I"
+        };
+        Some(Self { tier, division, lp })
+    }
+
+    /// The rank as one number, 100 per division: Iron IV 0 LP is 0, Master 0 LP is
+    /// 2800 and Master+ LP counts on from there.
+    pub fn ladder_value(&self) -> i32 {
+        if !has_divisions(self.tier) {
+            return APEX_FLOOR + self.lp;
+        }
+        let division = DIVISIONS.iter().position(|d| *d == self.division).unwrap_or(0) as i32;
+        tier_floor(self.tier) + division * DIVISION_SIZE + self.lp
+    }
+
+    /// The rank at `value` on the ladder, clamped at Iron IV 0 LP. Above Master 0 LP the
+    /// tier is `apex` (Master when it has divisions), the LP alone can't tell where the
+    /// Grandmaster and Challenger cutoffs were.
+    pub fn from_ladder_value(value: i32, apex: Tier) -> Self {
+        let value = value.max(0);
+        if value >= APEX_FLOOR {
+            let tier = if has_divisions(apex) { Tier::Master } else { apex };
+            return Self { tier, division: "I", lp: value - APEX_FLOOR };
+        }
+        let mut tier = Tier::Iron;
+        for _ in 0..value / TIER_SIZE {
+            tier = next_tier(tier);
+        }
+        let in_tier = value - tier_floor(tier);
+        Self {
+            tier,
+            division: DIVISIONS[(in_tier / DIVISION_SIZE) as usize],
+            lp: in_tier % DIVISION_SIZE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(tier: &str, division: &str, lp: i32) -> Rank {
+        Rank::parse(tier, division, lp).unwrap()
+    }
+
+    #[test]
+    fn parses_tiers_case_insensitively() {
+        assert_eq!("gold".parse::<Tier>(), Ok(Tier::Gold));
+        assert_eq!("Grandmaster".parse::<Tier>(), Ok(Tier::Grandmaster));
+        assert!("UNRANKED".parse::<Tier>().is_err());
+        assert_eq!(Rank::parse("SILVER", "V", 0), None);
+    }
+
+    #[test]
+    fn promotion_crosses_into_the_next_division_and_tier() {
+        let before = rank("GOLD", "II", 90);
+        let after = Rank::from_ladder_value(before.ladder_value() + 25, Tier::Gold);
+        assert_eq!(after, rank("GOLD", "I", 15));
+
+        let before = rank("GOLD", "I", 90);
+        let after = Rank::from_ladder_value(before.ladder_value() + 25, Tier::Gold);
+        assert_eq!(after, rank("PLATINUM", "IV", 15));
+    }
+
+    #[test]
+    fn demotion_drops_into_the_previous_division_and_tier() {
+        let before = rank("EMERALD", "III", 10);
+        let after = Rank::from_ladder_value(before.ladder_value() - 30, Tier::Emerald);
+        assert_eq!(after, rank("EMERALD", "IV", 80));
+
+        let before = rank("EMERALD", "IV", 10);
+        let after = Rank::from_ladder_value(before.ladder_value() - 30, Tier::Emerald);
+        assert_eq!(after, rank("PLATINUM", "I", 80));
+    }
+
+    #[test]
+    fn diamond_one_promotes_to_master() {
+        let before = rank("DIAMOND", "I", 85);
+        assert_eq!(before.ladder_value() + 15, APEX_FLOOR);
+
+        let after = Rank::from_ladder_value(before.ladder_value() + 30, Tier::Diamond);
+        assert_eq!(after, rank("MASTER", "I", 15));
+        assert_eq!(Rank::from_ladder_value(after.ladder_value() - 30, Tier::Master), before);
+    }
+
+    #[test]
+    fn apex_tiers_share_the_master_ladder() {
+        let master = rank("MASTER", "", 350);
+        let challenger = rank("CHALLENGER", "I", 350);
+        assert_eq!(master.ladder_value(), challenger.ladder_value());
+        assert_eq!(
+            Rank::from_ladder_value(challenger.ladder_value() + 20, Tier::Challenger),
+            rank("CHALLENGER", "I", 370)
+        );
+    }
+
+    #[test]
+    fn iron_four_is_the_floor() {
+        let iron = rank("IRON", "IV", 10);
+        assert_eq!(iron.ladder_value(), 10);
+        assert_eq!(Rank::from_ladder_value(iron.ladder_value() - 25, Tier::Iron), rank("IRON", "IV", 0));
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::header::HeaderMap;
+
+/// Limits of a development key, used until the first response tells us the real ones.
+const DEFAULT_APP_LIMITS: &str = "20:1,100:120";
+
+/// Fallback wait when a 429 comes back without a `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Window {
+    limit: u32,
+    period: Duration,
+}
+
+/// Parses a Riot rate limit header such as `20:1,100:120` into `(count, seconds)` pairs.
+fn parse_pairs(value: &str) -> Vec<(u32, u64)> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (a, b) = pair.trim().split_once(':')?;
+            Some((a.parse().ok()?, b.parse().ok()?))
+        })
+        .collect()
+}
+
+fn parse_windows(value: &str) -> Vec<Window> {
+    parse_pairs(value)
+        .into_iter()
+        .filter(|(limit, secs)| *limit > 0 && *secs > 0)
+        .map(|(limit, secs)| Window { limit, period: Duration::from_secs(secs) })
+        .collect()
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+#[derive(Default)]
+struct Bucket {
+    windows: Vec<Window>,
+    sent: VecDeque<Instant>,
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn with_limits(limits: &str) -> Self {
+        Self { windows: parse_windows(limits), ..Default::default() }
+    }
+
+    /// How long the caller has to wait before this bucket allows `tokens` more requests.
+    /// More tokens than a window holds go through once that window is empty.
+    fn wait_time(&mut self, now: Instant, tokens: u32) -> Duration {
+        let longest = self.windows.iter().map(|w| w.period).max().unwrap_or_default();
+        while self.sent.front().is_some_and(|t| now.duration_since(*t) >= longest) {
+            self.sent.pop_front();
+        }
+
+        let mut wait = self
+            .blocked_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+
+        for w in &self.windows {
+            let in_window: Vec<&Instant> = self
+                .sent
+                .iter()
+                .filter(|t| now.duration_since(**t) < w.period)
+                .collect();
+            let over = (in_window.len() + tokens as usize).saturating_sub(w.limit as usize);
+            if over > 0 && !in_window.is_empty() {
+                // The slots free up once the oldest requests that still count fall out of the window.
+                let oldest = in_window[over.min(in_window.len()) - 1];
+                wait = wait.max((*oldest + w.period).saturating_duration_since(now));
+            }
+        }
+        wait
+    }
+
+    /// Applies the limits and counts reported by Riot for this bucket.
+    fn observe(&mut self, limits: Option<&str>, counts: Option<&str>, now: Instant) {
+        if let Some(limits) = limits {
+            let windows = parse_windows(limits);
+            if !windows.is_empty() {
+                self.windows = windows;
+            }
+        }
+        // Other clients (the rest of the team behind the same proxy) share the key,
+        // so Riot's count can be ahead of ours. Block the window out when it is used up.
+        if let Some(counts) = counts {
+            for (count, secs) in parse_pairs(counts) {
+                let exhausted = self
+                    .windows
+                    .iter()
+                    .any(|w| w.period.as_secs() == secs && count >= w.limit);
+                if exhausted {
+                    self.block_for(Duration::from_secs(secs), now);
+                }
+            }
+        }
+    }
+
+    fn block_for(&mut self, wait: Duration, now: Instant) {
+        let until = now + wait;
+        if self.blocked_until.is_none_or(|current| current < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+/// Client-side token budget for the Riot API.
+///
+/// Application limits are tracked per routing value (`europe`, `americas`, `euw1`, ...)
+/// and method limits per routing value and endpoint, mirroring how Riot enforces them.
+/// Callers wait in [`RateLimiter::acquire`] instead of running into 429s.
+pub struct RateLimiter {
+    app: Mutex<HashMap<String, Bucket>>,
+    method: Mutex<HashMap<(String, &'static str), Bucket>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self { app: Mutex::new(HashMap::new()), method: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl RateLimiter {
+    /// Waits until both the application and the method budget for `routing` allow `tokens`
+    /// requests, then reserves slots for them. A batch call through the proxy that makes
+    /// one Riot request per item takes one token per item.
+    pub async fn acquire(&self, routing: &str, method: &'static str, tokens: u32) {
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut app = self.app.lock().unwrap();
+                let mut methods = self.method.lock().unwrap();
+                let app_bucket = app
+                    .entry(routing.to_string())
+                    .or_insert_with(|| Bucket::with_limits(DEFAULT_APP_LIMITS));
+                let method_bucket = methods.entry((routing.to_string(), method)).or_default();
+
+                let wait = app_bucket.wait_time(now, tokens).max(method_bucket.wait_time(now, tokens));
+                if wait.is_zero() {
+                    for _ in 0..tokens {
+                        app_bucket.sent.push_back(now);
+                        method_bucket.sent.push_back(now);
+                    }
+                    return;
+                }
+                wait
+            };
+            println!("[RateLimit] {routing} {method}: waiting {} ms", wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Updates the budgets from the `X-App-Rate-Limit*` and `X-Method-Rate-Limit*` headers.
+    pub fn observe(&self, routing: &str, method: &'static str, headers: &HeaderMap) {
+        let now = Instant::now();
+        self.app
+            .lock()
+            .unwrap()
+            .entry(routing.to_string())
+            .or_insert_with(|| Bucket::with_limits(DEFAULT_APP_LIMITS))
+            .observe(
+                header(headers, "x-app-rate-limit"),
+                header(headers, "x-app-rate-limit-count"),
+                now,
+            );
+        self.method
+            .lock()
+            .unwrap()
+            .entry((routing.to_string(), method))
+            .or_default()
+            .observe(
+                header(headers, "x-method-rate-limit"),
+                header(headers, "x-method-rate-limit-count"),
+                now,
+            );
+    }
+
+    /// Records a 429 and returns how long Riot asked us to back off.
+    pub fn on_rate_limited(&self, routing: &str, method: &'static str, headers: &HeaderMap) -> Duration {
+        let now = Instant::now();
+        let wait = retry_after(headers).unwrap_or(DEFAULT_RETRY_AFTER);
+
+        if header(headers, "x-rate-limit-type") == Some("application") {
+            self.app
+                .lock()
+                .unwrap()
+                .entry(routing.to_string())
+                .or_insert_with(|| Bucket::with_limits(DEFAULT_APP_LIMITS))
+                .block_for(wait, now);
+        } else {
+            // "method" and "service" limits only affect this endpoint.
+            self.method
+                .lock()
+                .unwrap()
+                .entry((routing.to_string(), method))
+                .or_default()
+                .block_for(wait, now);
+        }
+        wait
+    }
+}
+
+/// Reads the `Retry-After` header (in seconds).
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header(headers, "retry-after")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn take(bucket: &mut Bucket, now: Instant, tokens: u32) {
+        assert!(bucket.wait_time(now, tokens).is_zero());
+        for _ in 0..tokens {
+            bucket.sent.push_back(now);
+        }
+    }
+
+    #[test]
+    fn window_refills_once_the_oldest_request_falls_out() {
+        let start = Instant::now();
+        let mut bucket = Bucket::with_limits("2:1,3:10");
+
+        take(&mut bucket, start, 1);
+        take(&mut bucket, start + Duration::from_millis(400), 1);
+        assert_eq!(bucket.wait_time(start + Duration::from_millis(500), 1), Duration::from_millis(500));
+
+        // The short window has room again, the long one still counts both requests.
+        let later = start + Duration::from_secs(1);
+        take(&mut bucket, later, 1);
+        assert_eq!(bucket.wait_time(later, 1), Duration::from_secs(9));
+        assert!(bucket.wait_time(start + Duration::from_secs(10), 1).is_zero());
+    }
+
+    #[test]
+    fn several_tokens_wait_for_enough_free_slots() {
+        let start = Instant::now();
+        let mut bucket = Bucket::with_limits("5:1");
+
+        take(&mut bucket, start, 2);
+        take(&mut bucket, start + Duration::from_millis(200), 2);
+        // Three more need the first two to expire, not just one free slot.
+        assert_eq!(bucket.wait_time(start + Duration::from_millis(500), 1), Duration::ZERO);
+        assert_eq!(bucket.wait_time(start + Duration::from_millis(500), 3), Duration::from_millis(500));
+
+        // A batch larger than the window goes through once the window is empty.
+        assert_eq!(bucket.wait_time(start + Duration::from_millis(500), 8), Duration::from_millis(700));
+        assert!(bucket.wait_time(start + Duration::from_millis(1200), 8).is_zero());
+    }
+
+    #[test]
+    fn observed_counts_block_a_used_up_window() {
+        let now = Instant::now();
+        let mut bucket = Bucket::with_limits(DEFAULT_APP_LIMITS);
+        bucket.observe(Some("20:1,100:120"), Some("20:1,35:120"), now);
+        assert_eq!(bucket.wait_time(now, 1), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retry_after_is_read_in_seconds() {
+        assert_eq!(retry_after(&headers(&[("retry-after", "7")])), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after(&headers(&[("retry-after", "soon")])), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn application_429_blocks_the_whole_routing_value() {
+        let limiter = RateLimiter::default();
+        let wait = limiter.on_rate_limited(
+            "europe",
+            "match-v5.match",
+            &headers(&[("retry-after", "5"), ("x-rate-limit-type", "application")]),
+        );
+        assert_eq!(wait, Duration::from_secs(5));
+
+        let now = Instant::now();
+        let app_wait = limiter.app.lock().unwrap().get_mut("europe").unwrap().wait_time(now, 1);
+        assert!(app_wait > Duration::from_secs(4));
+        assert!(limiter.method.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn method_429_only_blocks_the_endpoint() {
+        let limiter = RateLimiter::default();
+        let wait = limiter.on_rate_limited(
+            "europe",
+            "match-v5.timeline",
+            &headers(&[("x-rate-limit-type", "method")]),
+        );
+        assert_eq!(wait, DEFAULT_RETRY_AFTER);
+
+        let now = Instant::now();
+        let mut methods = limiter.method.lock().unwrap();
+        let bucket = methods.get_mut(&("europe".to_string(), "match-v5.timeline")).unwrap();
+        assert!(!bucket.wait_time(now, 1).is_zero());
+        assert!(limiter.app.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::DianaError;
+
+/// A League of Legends server (platform routing value).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Platform {
+    BR1,
+    EUN1,
+    EUW1,
+    JP1,
+    KR,
+    LA1,
+    LA2,
+    ME1,
+    NA1,
+    OC1,
+    PH2,
+    RU,
+    SG2,
+    TH2,
+    TR1,
+    TW2,
+    VN2,
+}
+
+/// Regional routing value used by account-v1 and match-v5.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RegionalRoute {
+    Americas,
+    Asia,
+    Europe,
+    Sea,
+}
+
+impl Platform {
+    /// Host prefix for platform endpoints, e.g. `euw1` in `euw1.api.riotgames.com`.
+    pub fn as_str(&self) -> &'static str {
+        use Platform::*;
+        match self {
+            BR1 => "br1", EUN1 => "eun1", EUW1 => "euw1", JP1 => "jp1", KR => "kr",
+            LA1 => "la1", LA2 => "la2", ME1 => "me1", NA1 => "na1", OC1 => "oc1",
+            PH2 => "ph2", RU => "ru", SG2 => "sg2", TH2 => "th2", TR1 => "tr1",
+            TW2 => "tw2", VN2 => "vn2",
+        }
+    }
+
+    /// Name players know the server by, as shown in the client (`EUW`, `LAN`, ...).
+    pub fn short_name(&self) -> &'static str {
+        use Platform::*;
+        match self {
+            BR1 => "BR", EUN1 => "EUNE", EUW1 => "EUW", JP1 => "JP", KR => "KR",
+            LA1 => "LAN", LA2 => "LAS", ME1 => "ME", NA1 => "NA", OC1 => "OCE",
+            PH2 => "PH", RU => "RU", SG2 => "SG", TH2 => "TH", TR1 => "TR",
+            TW2 => "TW", VN2 => "VN",
+        }
+    }
+
+    /// Prefix Riot puts in front of match IDs from this server, e.g. `EUW1` in `EUW1_7012345678`.
+    pub fn match_id_prefix(&self) -> String {
+        self.as_str().to_ascii_uppercase()
+    }
+
+    /// Works out which server a match was played on from its ID.
+    pub fn from_match_id(match_id: &str) -> Result<Platform, DianaError> {
+        let (prefix, _) = match_id
+            .split_once('_')
+            .ok_or_else(|| DianaError::InvalidRegion(match_id.to_string()))?;
+        prefix.parse()
+    }
+
+    /// Route for Riot ID lookups. account-v1 is only served from
+    /// `americas`, `asia` and `europe`, so SEA servers use the nearest of those.
+    pub fn account_route(&self) -> RegionalRoute {
+        use Platform::*;
+        match self {
+            NA1 | BR1 | LA1 | LA2 | OC1 => RegionalRoute::Americas,
+            KR | JP1 | PH2 | SG2 | TH2 | TW2 | VN2 => RegionalRoute::Asia,
+            EUW1 | EUN1 | TR1 | RU | ME1 => RegionalRoute::Europe,
+        }
+    }
+
+    /// Route for match-v5, where the SEA servers have their own cluster.
+    pub fn match_route(&self) -> RegionalRoute {
+        use Platform::*;
+        match self {
+            NA1 | BR1 | LA1 | LA2 => RegionalRoute::Americas,
+            KR | JP1 => RegionalRoute::Asia,
+            EUW1 | EUN1 | TR1 | RU | ME1 => RegionalRoute::Europe,
+            OC1 | PH2 | SG2 | TH2 | TW2 | VN2 => RegionalRoute::Sea,
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = DianaError;
+
+    /// Accepts both the short names (`EUW`, `OCE`) and platform ids (`euw1`, `oc1`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Platform::*;
+        let platform = match s.trim().to_ascii_uppercase().as_str() {
+            "BR" | "BR1" => BR1,
+            "EUNE" | "EUN1" => EUN1,
+            "EUW" | "EUW1" => EUW1,
+            "JP" | "JP1" => JP1,
+            "KR" => KR,
+            "LAN" | "LA1" => LA1,
+            "LAS" | "LA2" => LA2,
+            "ME" | "ME1" => ME1,
+            "NA" | "NA1" => NA1,
+            "OCE" | "OC1" => OC1,
+            "PH" | "PH2" => PH2,
+            "RU" | "RU1" => RU,
+            "SG" | "SG2" => SG2,
+            "TH" | "TH2" => TH2,
+            "TR" | "TR1" => TR1,
+            "TW" | "TW2" => TW2,
+            "VN" | "VN2" => VN2,
+            _ => return Err(DianaError::InvalidRegion(s.to_string())),
+        };
+        Ok(platform)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.short_name())
+    }
+}
+
+impl RegionalRoute {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegionalRoute::Americas => "americas",
+            RegionalRoute::Asia => "asia",
+            RegionalRoute::Europe => "europe",
+            RegionalRoute::Sea => "sea",
+        }
+    }
+}
+
+impl FromStr for RegionalRoute {
+    type Err = DianaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "americas" => Ok(RegionalRoute::Americas),
+            "asia" => Ok(RegionalRoute::Asia),
+            "europe" => Ok(RegionalRoute::Europe),
+            "sea" => Ok(RegionalRoute::Sea),
+            _ => Err(DianaError::InvalidRegion(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for RegionalRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_short_names_and_platform_ids_in_any_case() {
+        assert_eq!("EUW".parse::<Platform>().unwrap(), Platform::EUW1);
+        assert_eq!("euw1".parse::<Platform>().unwrap(), Platform::EUW1);
+        assert_eq!(" oce ".parse::<Platform>().unwrap(), Platform::OC1);
+        assert_eq!("Lan".parse::<Platform>().unwrap(), Platform::LA1);
+        assert_eq!("ru1".parse::<Platform>().unwrap(), Platform::RU);
+    }
+
+    #[test]
+    fn unknown_platforms_are_invalid_regions() {
+        for input in ["", "EU", "euw2", "americas"] {
+            let err = input.parse::<Platform>().unwrap_err();
+            assert_eq!(err.code(), "invalid_region");
+        }
+    }
+
+    #[test]
+    fn sea_servers_look_up_accounts_nearby_but_have_their_own_matches() {
+        assert_eq!(Platform::OC1.account_route(), RegionalRoute::Americas);
+        assert_eq!(Platform::OC1.match_route(), RegionalRoute::Sea);
+        for platform in [Platform::PH2, Platform::SG2, Platform::TH2, Platform::TW2, Platform::VN2] {
+            assert_eq!(platform.account_route(), RegionalRoute::Asia);
+            assert_eq!(platform.match_route(), RegionalRoute::Sea);
+        }
+    }
+
+    #[test]
+    fn other_servers_share_one_route_for_accounts_and_matches() {
+        let cases = [
+            (Platform::EUW1, RegionalRoute::Europe),
+            (Platform::TR1, RegionalRoute::Europe),
+            (Platform::NA1, RegionalRoute::Americas),
+            (Platform::LA2, RegionalRoute::Americas),
+            (Platform::KR, RegionalRoute::Asia),
+            (Platform::JP1, RegionalRoute::Asia),
+        ];
+        for (platform, route) in cases {
+            assert_eq!((platform.account_route(), platform.match_route()), (route, route));
+        }
+    }
+
+    #[test]
+    fn match_ids_carry_their_platform() {
+        assert_eq!(Platform::from_match_id("EUW1_7012345678").unwrap(), Platform::EUW1);
+        assert_eq!(Platform::from_match_id("OC1_612345678").unwrap(), Platform::OC1);
+        assert_eq!(Platform::from_match_id("KR_7312345678").unwrap(), Platform::KR);
+    }
+
+    #[test]
+    fn match_ids_with_an_unknown_or_missing_prefix_are_rejected() {
+        for match_id in ["XX1_7012345678", "7012345678", "_7012345678", ""] {
+            let err = Platform::from_match_id(match_id).unwrap_err();
+            assert_eq!(err.code(), "invalid_region");
+        }
+    }
+}
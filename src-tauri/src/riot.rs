@@ -1,48 +1,46 @@
+use std::sync::Arc;
+
 use anyhow::{anyhow, Result};
-use tokio::sync::OnceCell;
-use reqwest::Client;
+use once_cell::sync::Lazy;
+use reqwest::{Client, RequestBuilder, StatusCode};
 use serde::de::DeserializeOwned;
 
+use crate::error::DianaError;
 use crate::models::{AccountDto, LeagueEntryDto, MatchDto, SummonerDto};
-use crate::db_proxy::{proxy_base_url, proxy_health_url};
+use crate::rate_limit::{self, RateLimiter};
+use crate::riot_source::{self, RiotSource, Route};
 
-pub async fn check_proxy_connectivity(client: &Client) -> Result<bool> {
-    let health_url = proxy_health_url();
-    
-    match client.get(&health_url).timeout(std::time::Duration::from_secs(5)).send().await {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_success() {
-                println!("[ProxyAPI] Proxy server is available at {}", health_url);
-                Ok(true)
-            } else {
-                println!("[ProxyAPI] Proxy server returned status {} at {}", status, health_url);
-                Ok(false)
-            }
-        }
-        Err(e) => {
-            println!("[ProxyAPI] Failed to connect to proxy server at {}: {}", health_url, e);
-            Ok(false)
-        }
-    }
+/// How many times a request that hit a 429 is queued again before giving up.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+static RATE_LIMITER: Lazy<RateLimiter> = Lazy::new(RateLimiter::default);
+
+/// HTTP client bound to the Riot backend selected for this run.
+#[derive(Clone)]
+pub struct RiotClient {
+    http: Client,
+    source: Arc<dyn RiotSource>,
 }
 
-static DDRAGON_VERSION: OnceCell<String> = OnceCell::const_new();
-
-pub fn map_region(region: &str) -> Option<(&'static str, &'static str)> {
-    match region.to_ascii_uppercase().as_str() {
-        "EUW" | "EUW1" => Some(("euw1", "europe")),
-        "EUNE" | "EUN1" => Some(("eun1", "europe")),
-        "NA" | "NA1" => Some(("na1", "americas")),
-        "KR" => Some(("kr", "asia")),
-        "JP" | "JP1" => Some(("jp1", "asia")),
-        "BR" | "BR1" => Some(("br1", "americas")),
-        "LAN" | "LA1" => Some(("la1", "americas")),
-        "LAS" | "LA2" => Some(("la2", "americas")),
-        "OCE" | "OC1" => Some(("oc1", "sea")),
-        "TR" | "TR1" => Some(("tr1", "europe")),
-        "RU" | "RU1" => Some(("ru", "europe")),
-        _ => None,
+impl RiotClient {
+    pub fn new(http: Client, source: Arc<dyn RiotSource>) -> Self {
+        Self { http, source }
+    }
+
+    /// Builds a client for the backend chosen by `RIOT_BACKEND`.
+    pub fn from_env() -> Result<Self> {
+        let http = Client::builder().user_agent("Diana/0.1.0").build()?;
+        let source: Arc<dyn RiotSource> = riot_source::source_from_env()?.into();
+        println!("[RiotAPI] Using {} backend", source.name());
+        Ok(Self::new(http, source))
+    }
+
+    pub fn http(&self) -> &Client {
+        &self.http
+    }
+
+    pub fn source(&self) -> &dyn RiotSource {
+        self.source.as_ref()
     }
 }
 
@@ -57,64 +55,124 @@ async fn get_from_proxy<T: DeserializeOwned>(
 
     if !status.is_success() {
         println!("[ProxyAPI] ERROR {} for {} → body: {}", status.as_u16(), url, text);
-        return Err(anyhow!("Proxy API {}: {}", status, text));
+        return Err(DianaError::from_riot_status(status.as_u16(), &text, None).into());
     }
 
-    let json = serde_json::from_str::<T>(&text)
-        .map_err(|e| anyhow!("Failed to decode JSON: {} → {}", e, text))?;
-    Ok(json)
+    decode_json(&text)
+}
+
+fn decode_json<T: DeserializeOwned>(text: &str) -> Result<T> {
+    serde_json::from_str::<T>(text)
+        .map_err(|e| anyhow!("Failed to decode JSON: {} → {}", e, text))
+}
+
+/// Sends a Riot API request through the shared rate limiter.
+///
+/// `routing` is the routing value the request is billed against (`europe`, `euw1`, ...)
+/// and `method` names the endpoint for method-level limits. `tokens` is how many Riot
+/// requests the call costs. A 429 is not an error here: the request waits out
+/// `Retry-After` and is queued again.
+async fn execute_riot(
+    request: RequestBuilder,
+    routing: &str,
+    method: &'static str,
+    url: &str,
+    tokens: u32,
+) -> Result<String> {
+    let mut attempt = 0;
+    loop {
+        RATE_LIMITER.acquire(routing, method, tokens).await;
+
+        let req = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("Request to {} cannot be retried", url))?;
+        let res = req.send().await?;
+        let status = res.status();
+        RATE_LIMITER.observe(routing, method, res.headers());
+
+        if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RATE_LIMIT_RETRIES {
+            let wait = RATE_LIMITER.on_rate_limited(routing, method, res.headers());
+            attempt += 1;
+            println!(
+                "[ProxyAPI] 429 for {} → retry {}/{} in {} ms",
+                url, attempt, MAX_RATE_LIMIT_RETRIES, wait.as_millis()
+            );
+            continue;
+        }
+
+        let retry_after = rate_limit::retry_after(res.headers()).map(|d| d.as_secs());
+        let text = res.text().await.unwrap_or_default();
+        if !status.is_success() {
+            println!("[ProxyAPI] ERROR {} for {} → body: {}", status.as_u16(), url, text);
+            return Err(DianaError::from_riot_status(status.as_u16(), &text, retry_after).into());
+        }
+        return Ok(text);
+    }
+}
+
+async fn get_riot<T: DeserializeOwned>(
+    client: &RiotClient,
+    route: Route<'_>,
+    method: &'static str,
+    path: &str,
+) -> Result<T> {
+    let url = client.source().url(route, path);
+    println!("[RiotAPI] GET {url}");
+    let request = client.source().authorize(client.http().get(&url));
+    let text = execute_riot(request, route.value(), method, &url, 1).await?;
+    decode_json(&text)
+}
+
+/// Replaces a bare 404 from Riot with a more specific error.
+fn map_not_found(err: anyhow::Error, not_found: impl FnOnce() -> DianaError) -> anyhow::Error {
+    match err.downcast_ref::<DianaError>() {
+        Some(e) if e.is_not_found() => not_found().into(),
+        _ => err,
+    }
 }
 
 pub async fn get_account_by_riot_id(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     name: &str,
     tag: &str,
 ) -> Result<AccountDto> {
-    let url = format!(
-        "{}/riot/account/v1/accounts/by-riot-id/{}/{}?region={}",
-        proxy_base_url(),
+    let path = format!(
+        "/riot/account/v1/accounts/by-riot-id/{}/{}",
         urlencoding::encode(name),
         urlencoding::encode(tag),
-        regional
     );
-    get_from_proxy(client, &url).await
+    get_riot(client, Route::Regional(regional), "account-v1.by-riot-id", &path)
+        .await
+        .map_err(|e| map_not_found(e, || DianaError::PlayerNotFound(format!("{}#{}", name, tag))))
 }
 
 pub async fn get_account_by_puuid(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     puuid: &str,
 ) -> Result<AccountDto> {
-    let url = format!(
-        "{}/riot/account/v1/accounts/by-puuid/{}?region={}",
-        proxy_base_url(), puuid, regional
-    );
-    get_from_proxy(client, &url).await
+    let path = format!("/riot/account/v1/accounts/by-puuid/{}", puuid);
+    get_riot(client, Route::Regional(regional), "account-v1.by-puuid", &path).await
 }
 
 pub async fn get_summoner_by_puuid(
-    client: &Client,
+    client: &RiotClient,
     platform: &str,
     puuid: &str,
 ) -> Result<SummonerDto> {
-    let url = format!(
-        "{}/lol/summoner/v4/summoners/by-puuid/{}?platform={}",
-        proxy_base_url(), puuid, platform
-    );
-    get_from_proxy(client, &url).await
+    let path = format!("/lol/summoner/v4/summoners/by-puuid/{}", puuid);
+    get_riot(client, Route::Platform(platform), "summoner-v4.by-puuid", &path).await
 }
 
 pub async fn get_rank_solo(
-    client: &Client,
+    client: &RiotClient,
     platform: &str,
     puuid: &str,
 ) -> Result<(Option<String>, Option<String>, Option<i32>)> {
-    let url = format!(
-        "{}/lol/league/v4/entries/by-puuid/{}?platform={}",
-        proxy_base_url(), puuid, platform
-    );
-    let entries: Vec<LeagueEntryDto> = get_from_proxy(client, &url).await?;
+    let path = format!("/lol/league/v4/entries/by-puuid/{}", puuid);
+    let entries: Vec<LeagueEntryDto> =
+        get_riot(client, Route::Platform(platform), "league-v4.entries-by-puuid", &path).await?;
     if let Some(solo) = entries.into_iter().find(|e| e.queueType == "RANKED_SOLO_5x5") {
         Ok((Some(solo.tier), Some(solo.rank), Some(solo.leaguePoints)))
     } else {
@@ -122,91 +180,210 @@ pub async fn get_rank_solo(
     }
 }
 
+/// Largest page match-v5 returns for a match ID query.
+pub const MATCH_ID_PAGE_SIZE: u32 = 100;
+
+/// Optional filters for [`get_match_ids`]. Times are epoch seconds, as Riot expects.
+#[derive(Clone, Debug, Default)]
+pub struct MatchIdFilter {
+    pub queue: Option<i32>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+impl MatchIdFilter {
+    fn query_string(&self) -> String {
+        let mut query = String::new();
+        if let Some(queue) = self.queue {
+            query.push_str(&format!("&queue={}", queue));
+        }
+        if let Some(start_time) = self.start_time {
+            query.push_str(&format!("&startTime={}", start_time));
+        }
+        if let Some(end_time) = self.end_time {
+            query.push_str(&format!("&endTime={}", end_time));
+        }
+        query
+    }
+}
+
 pub async fn get_match_ids(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     puuid: &str,
     start: u32,
     count: u32,
+    filter: &MatchIdFilter,
 ) -> Result<Vec<String>> {
-    let url = format!(
-        "{}/lol/match/v5/matches/by-puuid/{}/ids?region={}&start={}&count={}",
-        proxy_base_url(), puuid, regional, start, count
+    let path = format!(
+        "/lol/match/v5/matches/by-puuid/{}/ids?start={}&count={}{}",
+        puuid, start, count.min(MATCH_ID_PAGE_SIZE), filter.query_string()
     );
-    get_from_proxy(client, &url).await
+    get_riot(client, Route::Regional(regional), "match-v5.ids-by-puuid", &path).await
+}
+
+/// Walks a player's match history newest-first, one page of up to 100 IDs at a time.
+pub struct MatchIdPager<'a> {
+    client: &'a RiotClient,
+    regional: String,
+    puuid: String,
+    filter: MatchIdFilter,
+    next_start: u32,
+    done: bool,
+}
+
+impl<'a> MatchIdPager<'a> {
+    pub fn new(client: &'a RiotClient, regional: &str, puuid: &str, filter: MatchIdFilter) -> Self {
+        Self {
+            client,
+            regional: regional.to_string(),
+            puuid: puuid.to_string(),
+            filter,
+            next_start: 0,
+            done: false,
+        }
+    }
+
+    /// Resumes paging at `start` instead of the newest match.
+    pub fn starting_at(mut self, start: u32) -> Self {
+        self.next_start = start;
+        self
+    }
+
+    /// Fetches the next page, or `None` once the history is exhausted.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<String>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let ids = get_match_ids(
+            self.client,
+            &self.regional,
+            &self.puuid,
+            self.next_start,
+            MATCH_ID_PAGE_SIZE,
+            &self.filter,
+        )
+        .await?;
+
+        self.next_start += ids.len() as u32;
+        if (ids.len() as u32) < MATCH_ID_PAGE_SIZE {
+            self.done = true;
+        }
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ids))
+    }
 }
 
 pub async fn get_match_by_id(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     match_id: &str,
 ) -> Result<MatchDto> {
-    let url = format!(
-        "{}/lol/match/v5/matches/{}?region={}",
-        proxy_base_url(), match_id, regional
-    );
-    get_from_proxy(client, &url).await
+    let path = format!("/lol/match/v5/matches/{}", match_id);
+    get_riot(client, Route::Regional(regional), "match-v5.match", &path)
+        .await
+        .map_err(|e| map_not_found(e, || DianaError::MatchNotFound(match_id.to_string())))
 }
 
 pub async fn get_timeline_by_id(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     match_id: &str,
 ) -> Result<serde_json::Value> {
-    let url = format!(
-        "{}/lol/match/v5/matches/{}/timeline?region={}",
-        proxy_base_url(), match_id, regional
-    );
-    get_from_proxy(client, &url).await
-}
-
-pub async fn get_latest_ddragon_version(client: &reqwest::Client) -> Result<String> {
-    let v = DDRAGON_VERSION
-        .get_or_try_init(|| async {
-            let url = format!("{}/ddragon/versions", proxy_base_url());
-            let versions: Vec<String> = get_from_proxy(client, &url).await?;
-            versions
-                .into_iter()
-                .next()
-                .ok_or_else(|| anyhow!("No versions"))
-        })
-        .await?;
+    let path = format!("/lol/match/v5/matches/{}/timeline", match_id);
+    get_riot(client, Route::Regional(regional), "match-v5.timeline", &path)
+        .await
+        .map_err(|e| map_not_found(e, || DianaError::MatchNotFound(match_id.to_string())))
+}
 
-    Ok(v.clone())
+/// Newest DDragon version. Uncached, go through [`crate::app_state::DdragonCache`].
+pub async fn get_latest_ddragon_version(client: &RiotClient) -> Result<String> {
+    let url = client.source().ddragon_versions_url();
+    let versions: Vec<String> = get_from_proxy(client.http(), &url).await?;
+    versions
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No versions"))
 }
 
 pub async fn get_summoner_names_by_puuids(
-    client: &Client,
+    client: &RiotClient,
     regional: &str,
     puuids: &[String],
 ) -> Result<std::collections::HashMap<String, String>> {
-    let url = format!(
-        "{}/riot/account/v1/accounts/batch?region={}",
-        proxy_base_url(), regional
-    );
+    let Some(url) = client.source().batch_accounts_url(regional) else {
+        return get_summoner_names_one_by_one(client, regional, puuids).await;
+    };
     
     let body = serde_json::json!({
         "puuids": puuids
     });
     
     println!("[ProxyAPI] POST {url}");
-    let res = client
+    let request = client
+        .http()
         .post(&url)
         .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await?;
-    
-    let status = res.status();
-    let text = res.text().await.unwrap_or_default();
-
-    if !status.is_success() {
-        println!("[ProxyAPI] ERROR {} for {} → body: {}", status.as_u16(), url, text);
-        return Err(anyhow!("Proxy API {}: {}", status, text));
-    }
+        .json(&body);
+    // The proxy looks each PUUID up on its own, so the batch costs one request per PUUID.
+    let tokens = u32::try_from(puuids.len()).unwrap_or(u32::MAX).max(1);
+    let text = execute_riot(request, regional, "account-v1.by-puuid", &url, tokens).await?;
 
     let summoner_names: std::collections::HashMap<String, String> = serde_json::from_str(&text)
         .map_err(|e| anyhow!("Failed to decode JSON: {} → {}", e, text))?;
     
     Ok(summoner_names)
 }
+
+/// Resolves Riot IDs without a batch endpoint, falling back to a short placeholder
+/// like the proxy does when a single lookup fails.
+async fn get_summoner_names_one_by_one(
+    client: &RiotClient,
+    regional: &str,
+    puuids: &[String],
+) -> Result<std::collections::HashMap<String, String>> {
+    let mut names = std::collections::HashMap::new();
+    for puuid in puuids {
+        match get_account_by_puuid(client, regional, puuid).await {
+            Ok(acct) => {
+                let display = if acct.tagLine.is_empty() {
+                    acct.gameName
+                } else {
+                    format!("{}#{}", acct.gameName, acct.tagLine)
+                };
+                names.insert(puuid.clone(), display);
+            }
+            Err(e) => {
+                println!("[RiotAPI] Failed to fetch account for PUUID {}: {}", puuid, e);
+                let short: String = puuid.chars().take(8).collect();
+                names.insert(puuid.clone(), format!("Player {}", short));
+            }
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_id_filter_query_string() {
+        assert_eq!(MatchIdFilter::default().query_string(), "");
+
+        let filter = MatchIdFilter {
+            queue: Some(420),
+            start_time: Some(1_700_000_000),
+            end_time: Some(1_710_000_000),
+        };
+        assert_eq!(
+            filter.query_string(),
+            "&queue=420&startTime=1700000000&endTime=1710000000"
+        );
+
+        let since = MatchIdFilter { start_time: Some(1_700_000_000), ..Default::default() };
+        assert_eq!(since.query_string(), "&startTime=1700000000");
+    }
+}
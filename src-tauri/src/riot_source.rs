@@ -0,0 +1,130 @@
+use std::env;
+
+use anyhow::{anyhow, Result};
+use reqwest::RequestBuilder;
+
+use crate::db_proxy::{proxy_base_url, proxy_health_url};
+
+/// Which host a Riot API path is served from.
+#[derive(Clone, Copy, Debug)]
+pub enum Route<'a> {
+    /// Regional routing value such as `europe` or `americas` (account-v1, match-v5).
+    Regional(&'a str),
+    /// Platform routing value such as `euw1` or `kr` (summoner-v4, league-v4).
+    Platform(&'a str),
+}
+
+impl Route<'_> {
+    pub fn value(&self) -> &str {
+        match self {
+            Route::Regional(v) | Route::Platform(v) => v,
+        }
+    }
+}
+
+/// Backend that Riot API requests are sent to.
+pub trait RiotSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Full URL for a Riot API `path` (which may carry its own query string).
+    fn url(&self, route: Route<'_>, path: &str) -> String;
+
+    /// Adds whatever authentication the backend needs.
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+    }
+
+    fn ddragon_versions_url(&self) -> String;
+
+    /// Endpoint resolving many puuids to Riot IDs in one call, if the backend has one.
+    fn batch_accounts_url(&self, _regional: &str) -> Option<String> {
+        None
+    }
+
+    /// Health endpoint that has to answer before requests are sent, if any.
+    fn health_url(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Sends everything through diana-proxy, which holds the API key.
+pub struct ProxySource {
+    base_url: String,
+}
+
+impl ProxySource {
+    pub fn new(base_url: String) -> Self {
+        Self { base_url }
+    }
+}
+
+impl RiotSource for ProxySource {
+    fn name(&self) -> &'static str {
+        "proxy"
+    }
+
+    fn url(&self, route: Route<'_>, path: &str) -> String {
+        let sep = if path.contains('?') { '&' } else { '?' };
+        let (key, value) = match route {
+            Route::Regional(v) => ("region", v),
+            Route::Platform(v) => ("platform", v),
+        };
+        format!("{}{}{}{}={}", self.base_url, path, sep, key, value)
+    }
+
+    fn ddragon_versions_url(&self) -> String {
+        format!("{}/ddragon/versions", self.base_url)
+    }
+
+    fn batch_accounts_url(&self, regional: &str) -> Option<String> {
+        Some(format!("{}/riot/account/v1/accounts/batch?region={}", self.base_url, regional))
+    }
+
+    fn health_url(&self) -> Option<String> {
+        Some(proxy_health_url())
+    }
+}
+
+/// Calls `*.api.riotgames.com` directly with the user's own key.
+pub struct DirectSource {
+    api_key: String,
+}
+
+impl DirectSource {
+    pub fn new(api_key: String) -> Self {
+        Self { api_key }
+    }
+}
+
+impl RiotSource for DirectSource {
+    fn name(&self) -> &'static str {
+        "direct"
+    }
+
+    fn url(&self, route: Route<'_>, path: &str) -> String {
+        format!("https://{}.api.riotgames.com{}", route.value(), path)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header("X-Riot-Token", &self.api_key)
+    }
+
+    fn ddragon_versions_url(&self) -> String {
+        "https://ddragon.leagueoflegends.com/api/versions.json".to_string()
+    }
+}
+
+/// Picks the backend from `RIOT_BACKEND` (`proxy` or `direct`, default `proxy`).
+/// The direct backend reads its key from `RIOT_API_KEY`.
+pub fn source_from_env() -> Result<Box<dyn RiotSource>> {
+    let backend = env::var("RIOT_BACKEND").unwrap_or_else(|_| "proxy".to_string());
+    match backend.to_ascii_lowercase().as_str() {
+        "proxy" => Ok(Box::new(ProxySource::new(proxy_base_url()))),
+        "direct" => {
+            let api_key = env::var("RIOT_API_KEY")
+                .map_err(|_| anyhow!("RIOT_BACKEND=direct requires RIOT_API_KEY to be set"))?;
+            Ok(Box::new(DirectSource::new(api_key)))
+        }
+        other => Err(anyhow!("Unknown RIOT_BACKEND '{}', expected 'proxy' or 'direct'", other)),
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::models::RoleStats;
+
+/// `mapId` of Summoner's Rift, the only map with roles.
+pub const SUMMONERS_RIFT: i32 = 11;
+
+/// `teamPosition` values of Summoner's Rift, top to support.
+pub const ROLES: [&str; 5] = ["TOP", "JUNGLE", "MIDDLE", "BOTTOM", "UTILITY"];
+
+/// One game of the player, as read for the role breakdown. Remakes are left out.
+#[derive(sqlx::FromRow, Deserialize, Debug)]
+pub struct RoleGame {
+    pub team_position: Option<String>,
+    pub champion_name: Option<String>,
+    /// Known `teamPosition`s of the player's teammates, comma separated.
+    pub teammate_positions: Option<String>,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    pub cs: i32,
+    pub gold_earned: i32,
+    pub vision_score: i32,
+    pub win: bool,
+    pub game_duration: Option<i32>,
+    pub team_kills: Option<i64>,
+}
+
+/// How often a champion was played in each role, across every stored game.
+#[derive(sqlx::FromRow, Deserialize, Debug)]
+pub struct ChampionRole {
+    pub champion_name: String,
+    pub team_position: String,
+    pub games: i64,
+}
+
+/// Champions of `games` whose role has to be inferred.
+pub fn champions_without_role(games: &[RoleGame]) -> Vec<String> {
+    let mut champions: Vec<String> = games
+        .iter()
+        .filter(|g| g.team_position.is_none())
+        .filter_map(|g| g.champion_name.clone())
+        .collect();
+    champions.sort();
+    champions.dedup();
+    champions
+}
+
+/// Role of a game with an empty `teamPosition`: the one the four teammates left free
+/// if theirs are known, otherwise the role the champion is played in most.
+fn infer_role(game: &RoleGame, champion_roles: &HashMap<&str, &str>) -> Option<&'static str> {
+    let taken: Vec<&str> = game
+        .teammate_positions
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .collect();
+    let free: Vec<&'static str> = ROLES.iter().copied().filter(|r| !taken.contains(r)).collect();
+    if let [role] = free.as_slice() {
+        return Some(role);
+    }
+
+    let main_role = champion_roles.get(game.champion_name.as_deref()?)?;
+    ROLES.iter().copied().find(|r| r == main_role)
+}
+
+/// Role each champion is played in most. A tie goes to the role first in [`ROLES`].
+fn main_roles(champion_roles: &[ChampionRole]) -> HashMap<&str, &str> {
+    let mut main_roles: HashMap<&str, (&str, i64, usize)> = HashMap::new();
+    for c in champion_roles {
+        let Some(order) = ROLES.iter().position(|r| *r == c.team_position) else {
+            continue;
+        };
+        let entry = main_roles.entry(&c.champion_name).or_insert((&c.team_position, 0, order));
+        if (c.games, std::cmp::Reverse(order)) > (entry.1, std::cmp::Reverse(entry.2)) {
+            *entry = (&c.team_position, c.games, order);
+        }
+    }
+    main_roles.into_iter().map(|(champ, (role, _, _))| (champ, role)).collect()
+}
+
+/// Stats per role, in [`ROLES`] order. Games whose role can't be told are left out.
+pub fn role_stats(games: &[RoleGame], champion_roles: &[ChampionRole]) -> Vec<RoleStats> {
+    let main_roles = main_roles(champion_roles);
+
+    #[derive(Default)]
+    struct Totals {
+        games: i32,
+        wins: i32,
+        inferred: i32,
+        kills: i64,
+        deaths: i64,
+        assists: i64,
+        cs: i64,
+        gold: i64,
+        vision: i64,
+        team_kills: i64,
+        minutes: f32,
+    }
+    let mut totals: Vec<Totals> = ROLES.iter().map(|_| Totals::default()).collect();
+
+    for game in games {
+        let (role, inferred) = match game.team_position.as_deref() {
+            Some(position) => (ROLES.iter().copied().find(|r| *r == position), false),
+            None => (infer_role(game, &main_roles), true),
+        };
+        let Some(idx) = role.and_then(|r| ROLES.iter().position(|x| *x == r)) else {
+            continue;
+        };
+        let t = &mut totals[idx];
+        t.games += 1;
+        t.wins += game.win as i32;
+        t.inferred += inferred as i32;
+        t.kills += game.kills as i64;
+        t.deaths += game.deaths as i64;
+        t.assists += game.assists as i64;
+        t.cs += game.cs as i64;
+        t.gold += game.gold_earned as i64;
+        t.vision += game.vision_score as i64;
+        t.team_kills += game.team_kills.unwrap_or(0);
+        t.minutes += game.game_duration.unwrap_or(0) as f32 / 60.0;
+    }
+
+    let per_min = |value: i64, minutes: f32| if minutes > 0.0 { value as f32 / minutes } else { 0.0 };
+    ROLES
+        .iter()
+        .zip(totals)
+        .map(|(role, t)| RoleStats {
+            role: role.to_string(),
+            games: t.games,
+            wins: t.wins,
+            winrate: if t.games > 0 { t.wins as f32 / t.games as f32 * 100.0 } else { 0.0 },
+            kda: if t.deaths > 0 {
+                (t.kills + t.assists) as f32 / t.deaths as f32
+            } else {
+                (t.kills + t.assists) as f32
+            },
+            cs_per_min: per_min(t.cs, t.minutes),
+            gold_per_min: per_min(t.gold, t.minutes),
+            vision_per_min: per_min(t.vision, t.minutes),
+            kill_participation: if t.team_kills > 0 {
+                (t.kills + t.assists) as f32 / t.team_kills as f32 * 100.0
+            } else {
+                0.0
+            },
+            inferred_games: t.inferred,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(team_position: &str, champion: &str, teammates: &str) -> RoleGame {
+        RoleGame {
+            team_position: Some(team_position.to_string()).filter(|p| !p.is_empty()),
+            champion_name: Some(champion.to_string()),
+            teammate_positions: Some(teammates.to_string()).filter(|p| !p.is_empty()),
+            kills: 4,
+            deaths: 2,
+            assists: 6,
+            cs: 180,
+            gold_earned: 10_000,
+            vision_score: 20,
+            win: true,
+            game_duration: Some(1800),
+            team_kills: Some(20),
+        }
+    }
+
+    fn champion_role(champion: &str, role: &str, games: i64) -> ChampionRole {
+        ChampionRole {
+            champion_name: champion.to_string(),
+            team_position: role.to_string(),
+            games,
+        }
+    }
+
+    /// `(games, inferred_games)` of `role`.
+    fn counts(stats: &[RoleStats], role: &str) -> (i32, i32) {
+        let s = stats.iter().find(|s| s.role == role).unwrap();
+        (s.games, s.inferred_games)
+    }
+
+    #[test]
+    fn known_positions_are_taken_as_is() {
+        let stats = role_stats(&[game("TOP", "Garen", ""), game("UTILITY", "Lulu", "")], &[]);
+        let roles: Vec<&str> = stats.iter().map(|s| s.role.as_str()).collect();
+        assert_eq!(roles, ROLES);
+        assert_eq!(counts(&stats, "TOP"), (1, 0));
+        assert_eq!(counts(&stats, "UTILITY"), (1, 0));
+        assert_eq!(counts(&stats, "MIDDLE"), (0, 0));
+    }
+
+    #[test]
+    fn role_left_free_by_teammates_wins_over_the_champion() {
+        let games = [game("", "Ahri", "TOP,MIDDLE,BOTTOM,UTILITY")];
+        let stats = role_stats(&games, &[champion_role("Ahri", "MIDDLE", 50)]);
+        assert_eq!(counts(&stats, "JUNGLE"), (1, 1));
+        assert_eq!(counts(&stats, "MIDDLE"), (0, 0));
+    }
+
+    #[test]
+    fn champion_main_role_fills_in_when_teammates_are_unknown() {
+        let games = [game("", "Ahri", "TOP,JUNGLE")];
+        let champion_roles = [champion_role("Ahri", "UTILITY", 2), champion_role("Ahri", "MIDDLE", 40)];
+        let stats = role_stats(&games, &champion_roles);
+        assert_eq!(counts(&stats, "MIDDLE"), (1, 1));
+    }
+
+    #[test]
+    fn main_role_ties_go_to_the_first_role() {
+        let games = [game("", "Sett", "")];
+        for champion_roles in [
+            [champion_role("Sett", "UTILITY", 5), champion_role("Sett", "TOP", 5)],
+            [champion_role("Sett", "TOP", 5), champion_role("Sett", "UTILITY", 5)],
+        ] {
+            let stats = role_stats(&games, &champion_roles);
+            assert_eq!(counts(&stats, "TOP"), (1, 1));
+            assert_eq!(counts(&stats, "UTILITY"), (0, 0));
+        }
+    }
+
+    #[test]
+    fn games_without_a_role_to_infer_are_left_out() {
+        let stats = role_stats(&[game("", "Zoe", "TOP")], &[champion_role("Ahri", "MIDDLE", 3)]);
+        assert!(stats.iter().all(|s| s.games == 0));
+        assert_eq!(champions_without_role(&[game("", "Zoe", ""), game("TOP", "Garen", "")]), ["Zoe"]);
+    }
+
+    #[test]
+    fn kill_participation_is_a_percentage_like_winrate() {
+        let stats = role_stats(&[game("TOP", "Garen", "")], &[]);
+        let top = stats.iter().find(|s| s.role == "TOP").unwrap();
+        assert_eq!(top.winrate, 100.0);
+        assert_eq!(top.kill_participation, 50.0);
+    }
+}
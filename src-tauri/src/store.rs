@@ -0,0 +1,740 @@
+use std::collections::HashSet;
+use std::env;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use sqlx::migrate::Migrator;
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    db, db_proxy, db_sqlite,
+    match_summary::MatchSummary,
+    models::{
+        BackfillCheckpoint, DailyActivityEntry, DbMatchRow, DbSummoner, DbTeammate, MatchDetails,
+        MatchupStats, RankSnapshot, RankStep, RoleStats, SchemaVersion, Streaks, TimelineFrame,
+    },
+    queues::QueueGroup,
+};
+
+/// Columns of a new `match_details` row.
+pub struct NewMatchDetails<'a> {
+    pub match_id: &'a str,
+    pub entry_puuid: &'a str,
+    pub game_version: Option<&'a str>,
+    pub game_creation: Option<i64>,
+    pub game_start: Option<i64>,
+    pub game_end: Option<i64>,
+    pub game_duration: Option<i32>,
+    pub game_mode: Option<&'a str>,
+    pub game_type: Option<&'a str>,
+    pub queue_type: Option<i32>,
+    pub map_name: Option<i32>,
+    pub participants: &'a Value,
+    pub teams: &'a Value,
+}
+
+/// Columns of a `summoners` row, as refreshed from Riot.
+pub struct NewSummoner<'a> {
+    pub puuid: &'a str,
+    pub game_name: &'a str,
+    pub tag_line: &'a str,
+    pub region: &'a str,
+    pub match_region_prefix: &'a str,
+    pub region_group: &'a str,
+    pub tier: Option<&'a str>,
+    pub rank: Option<&'a str>,
+    pub lp: Option<i32>,
+}
+
+/// Writes that are applied together or not at all. Dropping it without
+/// [`StoreTx::commit`] discards them.
+#[async_trait]
+pub trait StoreTx: Send {
+    /// Returns the row's `mid`. Only use it within this transaction, some stores
+    /// hand out a provisional one until the commit.
+    async fn insert_match_details(&mut self, row: NewMatchDetails<'_>) -> Result<i64>;
+
+    async fn insert_timeline_frames(
+        &mut self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()>;
+
+    async fn commit(self: Box<Self>) -> Result<()>;
+}
+
+/// Where Diana keeps summoners, matches and everything computed from them.
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Health endpoint of the server behind the store, if there is one.
+    fn health_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Brings the schema up to date. Runs once at startup.
+    async fn migrate(&self) -> Result<()>;
+
+    async fn schema_version(&self) -> Result<SchemaVersion>;
+
+    async fn begin(&self) -> Result<Box<dyn StoreTx + '_>>;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn upsert_summoner(&self, summoner: NewSummoner<'_>) -> Result<()>;
+
+    async fn get_summoner(&self, puuid: &str) -> Result<Option<DbSummoner>>;
+
+    async fn match_exists(&self, match_id: &str) -> Result<bool>;
+
+    /// Which of `match_ids` are already stored.
+    async fn matches_exist(&self, match_ids: &[String]) -> Result<HashSet<String>>;
+
+    /// Earliest `gameCreation` among the stored ones of `match_ids`.
+    async fn oldest_game_creation(&self, match_ids: &[String]) -> Result<Option<i64>>;
+
+    /// Most recent stored match of `puuid`, the anchor for incremental syncs.
+    async fn latest_match_for_puuid(&self, puuid: &str) -> Result<Option<DbMatchRow>>;
+
+    async fn get_recent_matches(&self, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>>;
+
+    /// Stored matches of `puuid` that have no timeline frames yet, newest first. Matches
+    /// marked with [`Store::mark_timeline_unavailable`] are left out.
+    async fn get_matches_missing_timeline(&self, puuid: &str, limit: i64)
+        -> Result<Vec<DbMatchRow>>;
+
+    /// Records that Riot has no usable timeline for the match, so it is not retried.
+    async fn mark_timeline_unavailable(&self, mid: i64, reason: &str) -> Result<()>;
+
+    /// Stores all frames of an already stored match at once.
+    async fn insert_timeline_frames(
+        &self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()>;
+
+    /// Games, wins, losses, KDA, winrate, streaks and top champions over the games of
+    /// `puuid` in `queue`, cached in `player_summary` per (puuid, queue).
+    async fn compute_player_summary(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<(i32, i32, i32, f32, f32, Streaks, Value)>;
+
+    /// Games per day over the last 30 days.
+    async fn get_daily_activity(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<Vec<DailyActivityEntry>>;
+
+    /// Records the solo queue rank as just fetched from Riot.
+    async fn save_rank_snapshot(&self, puuid: &str, snapshot: &RankSnapshot) -> Result<()>;
+
+    /// LP history over the latest ranked solo games, oldest first. Exact where rank
+    /// snapshots pin the LP around a game, estimated otherwise.
+    async fn compute_rank_progress_and_cache(
+        &self,
+        puuid: &str,
+        current_tier: &str,
+        current_division: &str,
+        current_lp: i32,
+    ) -> Result<Vec<RankStep>>;
+
+    /// Stats per role over the Summoner's Rift games of `puuid` in `queue`, one entry
+    /// for each of [`crate::roles::ROLES`]. Games without a `teamPosition` get one inferred.
+    async fn get_role_stats(&self, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>>;
+
+    /// Record of `puuid` per own champion against each lane opponent's champion, for
+    /// matchups played at least `min_games` times in `queue`.
+    async fn get_matchup_stats(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<MatchupStats>>;
+
+    /// Players seen on the same team as `puuid` at least `min_games` times in `queue`,
+    /// most games together first.
+    async fn get_teammates(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<DbTeammate>>;
+
+    /// Summaries of `match_ids` for `puuid`, in the same order. Matches that are not
+    /// stored, or that `puuid` did not play in, are left out.
+    async fn summarize_matches(
+        &self,
+        match_ids: &[String],
+        puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<Vec<MatchSummary>>;
+
+    async fn get_match_details(
+        &self,
+        match_id: &str,
+        user_puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<MatchDetails>;
+
+    async fn get_backfill_checkpoint(&self, puuid: &str) -> Result<Option<BackfillCheckpoint>>;
+
+    async fn save_backfill_checkpoint(&self, checkpoint: &BackfillCheckpoint) -> Result<()>;
+}
+
+/// Opens the store picked by `DIANA_STORE`: `proxy` (default) goes through diana-proxy,
+/// `postgres` connects straight to `DATABASE_URL` and `sqlite` uses a database file in
+/// the app data dir, so nothing else has to be running.
+pub async fn store_from_env(app: &AppHandle, http: &Client) -> Result<Arc<dyn Store>> {
+    let kind = env::var("DIANA_STORE").unwrap_or_else(|_| "proxy".to_string());
+    let store: Arc<dyn Store> = match kind.to_ascii_lowercase().as_str() {
+        "proxy" => Arc::new(db_proxy::init_pool(http.clone())),
+        "postgres" => Arc::new(db::init_pool()?),
+        "sqlite" => {
+            let data_dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| anyhow!("Could not resolve the app data dir: {}", e))?;
+            Arc::new(db_sqlite::init_pool(&data_dir).await?)
+        }
+        other => {
+            return Err(anyhow!(
+                "Unknown DIANA_STORE '{}', expected 'proxy', 'postgres' or 'sqlite'",
+                other
+            ))
+        }
+    };
+    eprintln!("[STORE] Using {} store", kind.to_ascii_lowercase());
+    Ok(store)
+}
+
+fn latest_version(migrator: &Migrator) -> i64 {
+    migrator.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+#[async_trait]
+impl StoreTx for db_proxy::ProxyTx {
+    async fn insert_match_details(&mut self, row: NewMatchDetails<'_>) -> Result<i64> {
+        db_proxy::insert_match_details_tx(self, row).await
+    }
+
+    async fn insert_timeline_frames(
+        &mut self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db_proxy::insert_timeline_frames_tx(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        db_proxy::ProxyTx::commit(*self).await
+    }
+}
+
+#[async_trait]
+impl Store for db_proxy::ProxyPool {
+    fn health_url(&self) -> Option<String> {
+        Some(db_proxy::proxy_health_url())
+    }
+
+    /// diana-proxy applies the same migration files to its database when it starts,
+    /// recorded in `_sqlx_migrations` like sqlx does.
+    async fn migrate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn schema_version(&self) -> Result<SchemaVersion> {
+        Ok(SchemaVersion {
+            store: "proxy".to_string(),
+            version: db_proxy::schema_version(self).await?,
+            latest: latest_version(&db::MIGRATOR),
+        })
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StoreTx + '_>> {
+        Ok(Box::new(db_proxy::ProxyPool::begin(self).await?))
+    }
+
+    async fn upsert_summoner(&self, summoner: NewSummoner<'_>) -> Result<()> {
+        db_proxy::upsert_summoner(self, summoner).await
+    }
+
+    async fn get_summoner(&self, puuid: &str) -> Result<Option<DbSummoner>> {
+        db_proxy::get_summoner(self, puuid).await
+    }
+
+    async fn match_exists(&self, match_id: &str) -> Result<bool> {
+        db_proxy::match_exists(self, match_id).await
+    }
+
+    async fn matches_exist(&self, match_ids: &[String]) -> Result<HashSet<String>> {
+        db_proxy::matches_exist(self, match_ids).await
+    }
+
+    async fn oldest_game_creation(&self, match_ids: &[String]) -> Result<Option<i64>> {
+        db_proxy::oldest_game_creation(self, match_ids).await
+    }
+
+    async fn latest_match_for_puuid(&self, puuid: &str) -> Result<Option<DbMatchRow>> {
+        db_proxy::latest_match_for_puuid(self, puuid).await
+    }
+
+    async fn get_recent_matches(&self, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
+        db_proxy::get_recent_matches(self, puuid, limit).await
+    }
+
+    async fn get_matches_missing_timeline(
+        &self,
+        puuid: &str,
+        limit: i64,
+    ) -> Result<Vec<DbMatchRow>> {
+        db_proxy::get_matches_missing_timeline(self, puuid, limit).await
+    }
+
+    async fn mark_timeline_unavailable(&self, mid: i64, reason: &str) -> Result<()> {
+        db_proxy::mark_timeline_unavailable(self, mid, reason).await
+    }
+
+    async fn insert_timeline_frames(
+        &self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db_proxy::insert_timeline_frames(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn compute_player_summary(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<(i32, i32, i32, f32, f32, Streaks, Value)> {
+        db_proxy::compute_player_summary(self, puuid, queue).await
+    }
+
+    async fn get_daily_activity(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<Vec<DailyActivityEntry>> {
+        db_proxy::get_daily_activity(self, puuid, queue).await
+    }
+
+    async fn save_rank_snapshot(&self, puuid: &str, snapshot: &RankSnapshot) -> Result<()> {
+        db_proxy::save_rank_snapshot(self, puuid, snapshot).await
+    }
+
+    async fn compute_rank_progress_and_cache(
+        &self,
+        puuid: &str,
+        current_tier: &str,
+        current_division: &str,
+        current_lp: i32,
+    ) -> Result<Vec<RankStep>> {
+        db_proxy::compute_rank_progress_and_cache(
+            self,
+            puuid,
+            current_tier,
+            current_division,
+            current_lp,
+        )
+        .await
+    }
+
+    async fn get_role_stats(&self, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>> {
+        db_proxy::get_role_stats(self, puuid, queue).await
+    }
+
+    async fn get_matchup_stats(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<MatchupStats>> {
+        db_proxy::get_matchup_stats(self, puuid, queue, min_games).await
+    }
+
+    async fn get_teammates(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<DbTeammate>> {
+        db_proxy::get_teammates(self, puuid, queue, min_games).await
+    }
+
+    async fn summarize_matches(
+        &self,
+        match_ids: &[String],
+        puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<Vec<MatchSummary>> {
+        db_proxy::summarize_matches(self, match_ids, puuid, ddragon_version).await
+    }
+
+    async fn get_match_details(
+        &self,
+        match_id: &str,
+        user_puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<MatchDetails> {
+        db_proxy::get_match_details_from_db(self, match_id, user_puuid, ddragon_version).await
+    }
+
+    async fn get_backfill_checkpoint(&self, puuid: &str) -> Result<Option<BackfillCheckpoint>> {
+        db_proxy::get_backfill_checkpoint(self, puuid).await
+    }
+
+    async fn save_backfill_checkpoint(&self, checkpoint: &BackfillCheckpoint) -> Result<()> {
+        db_proxy::save_backfill_checkpoint(self, checkpoint).await
+    }
+}
+
+#[async_trait]
+impl StoreTx for db::PgTx<'static> {
+    async fn insert_match_details(&mut self, row: NewMatchDetails<'_>) -> Result<i64> {
+        db::insert_match_details_tx(self, row).await
+    }
+
+    async fn insert_timeline_frames(
+        &mut self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db::insert_timeline_frames_tx(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        sqlx::Transaction::commit(*self).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for db::PgPool {
+    async fn migrate(&self) -> Result<()> {
+        db::migrate(self).await
+    }
+
+    async fn schema_version(&self) -> Result<SchemaVersion> {
+        Ok(SchemaVersion {
+            store: "postgres".to_string(),
+            version: db::schema_version(self).await?,
+            latest: latest_version(&db::MIGRATOR),
+        })
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StoreTx + '_>> {
+        Ok(Box::new(sqlx::Pool::begin(self).await?))
+    }
+
+    async fn upsert_summoner(&self, summoner: NewSummoner<'_>) -> Result<()> {
+        db::upsert_summoner(self, summoner).await
+    }
+
+    async fn get_summoner(&self, puuid: &str) -> Result<Option<DbSummoner>> {
+        db::get_summoner(self, puuid).await
+    }
+
+    async fn match_exists(&self, match_id: &str) -> Result<bool> {
+        db::match_exists(self, match_id).await
+    }
+
+    async fn matches_exist(&self, match_ids: &[String]) -> Result<HashSet<String>> {
+        db::matches_exist(self, match_ids).await
+    }
+
+    async fn oldest_game_creation(&self, match_ids: &[String]) -> Result<Option<i64>> {
+        db::oldest_game_creation(self, match_ids).await
+    }
+
+    async fn latest_match_for_puuid(&self, puuid: &str) -> Result<Option<DbMatchRow>> {
+        db::latest_match_for_puuid(self, puuid).await
+    }
+
+    async fn get_recent_matches(&self, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
+        db::get_recent_matches(self, puuid, limit).await
+    }
+
+    async fn get_matches_missing_timeline(
+        &self,
+        puuid: &str,
+        limit: i64,
+    ) -> Result<Vec<DbMatchRow>> {
+        db::get_matches_missing_timeline(self, puuid, limit).await
+    }
+
+    async fn mark_timeline_unavailable(&self, mid: i64, reason: &str) -> Result<()> {
+        db::mark_timeline_unavailable(self, mid, reason).await
+    }
+
+    async fn insert_timeline_frames(
+        &self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db::insert_timeline_frames(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn compute_player_summary(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<(i32, i32, i32, f32, f32, Streaks, Value)> {
+        db::compute_player_summary(self, puuid, queue).await
+    }
+
+    async fn get_daily_activity(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<Vec<DailyActivityEntry>> {
+        db::get_daily_activity(self, puuid, queue).await
+    }
+
+    async fn save_rank_snapshot(&self, puuid: &str, snapshot: &RankSnapshot) -> Result<()> {
+        db::save_rank_snapshot(self, puuid, snapshot).await
+    }
+
+    async fn compute_rank_progress_and_cache(
+        &self,
+        puuid: &str,
+        current_tier: &str,
+        current_division: &str,
+        current_lp: i32,
+    ) -> Result<Vec<RankStep>> {
+        db::compute_rank_progress_and_cache(self, puuid, current_tier, current_division, current_lp)
+            .await
+    }
+
+    async fn get_role_stats(&self, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>> {
+        db::get_role_stats(self, puuid, queue).await
+    }
+
+    async fn get_matchup_stats(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<MatchupStats>> {
+        db::get_matchup_stats(self, puuid, queue, min_games).await
+    }
+
+    async fn get_teammates(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<DbTeammate>> {
+        db::get_teammates(self, puuid, queue, min_games).await
+    }
+
+    async fn summarize_matches(
+        &self,
+        match_ids: &[String],
+        puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<Vec<MatchSummary>> {
+        db::summarize_matches(self, match_ids, puuid, ddragon_version).await
+    }
+
+    async fn get_match_details(
+        &self,
+        match_id: &str,
+        user_puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<MatchDetails> {
+        db::get_match_details_from_db(self, match_id, user_puuid, ddragon_version).await
+    }
+
+    async fn get_backfill_checkpoint(&self, puuid: &str) -> Result<Option<BackfillCheckpoint>> {
+        db::get_backfill_checkpoint(self, puuid).await
+    }
+
+    async fn save_backfill_checkpoint(&self, checkpoint: &BackfillCheckpoint) -> Result<()> {
+        db::save_backfill_checkpoint(self, checkpoint).await
+    }
+}
+
+#[async_trait]
+impl StoreTx for db_sqlite::SqliteTx<'static> {
+    async fn insert_match_details(&mut self, row: NewMatchDetails<'_>) -> Result<i64> {
+        db_sqlite::insert_match_details_tx(self, row).await
+    }
+
+    async fn insert_timeline_frames(
+        &mut self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db_sqlite::insert_timeline_frames_tx(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        sqlx::Transaction::commit(*self).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for db_sqlite::SqlitePool {
+    async fn migrate(&self) -> Result<()> {
+        db_sqlite::migrate(self).await
+    }
+
+    async fn schema_version(&self) -> Result<SchemaVersion> {
+        Ok(SchemaVersion {
+            store: "sqlite".to_string(),
+            version: db_sqlite::schema_version(self).await?,
+            latest: latest_version(&db_sqlite::MIGRATOR),
+        })
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StoreTx + '_>> {
+        Ok(Box::new(sqlx::Pool::begin(self).await?))
+    }
+
+    async fn upsert_summoner(&self, summoner: NewSummoner<'_>) -> Result<()> {
+        db_sqlite::upsert_summoner(self, summoner).await
+    }
+
+    async fn get_summoner(&self, puuid: &str) -> Result<Option<DbSummoner>> {
+        db_sqlite::get_summoner(self, puuid).await
+    }
+
+    async fn match_exists(&self, match_id: &str) -> Result<bool> {
+        db_sqlite::match_exists(self, match_id).await
+    }
+
+    async fn matches_exist(&self, match_ids: &[String]) -> Result<HashSet<String>> {
+        db_sqlite::matches_exist(self, match_ids).await
+    }
+
+    async fn oldest_game_creation(&self, match_ids: &[String]) -> Result<Option<i64>> {
+        db_sqlite::oldest_game_creation(self, match_ids).await
+    }
+
+    async fn latest_match_for_puuid(&self, puuid: &str) -> Result<Option<DbMatchRow>> {
+        db_sqlite::latest_match_for_puuid(self, puuid).await
+    }
+
+    async fn get_recent_matches(&self, puuid: &str, limit: i64) -> Result<Vec<DbMatchRow>> {
+        db_sqlite::get_recent_matches(self, puuid, limit).await
+    }
+
+    async fn get_matches_missing_timeline(
+        &self,
+        puuid: &str,
+        limit: i64,
+    ) -> Result<Vec<DbMatchRow>> {
+        db_sqlite::get_matches_missing_timeline(self, puuid, limit).await
+    }
+
+    async fn mark_timeline_unavailable(&self, mid: i64, reason: &str) -> Result<()> {
+        db_sqlite::mark_timeline_unavailable(self, mid, reason).await
+    }
+
+    async fn insert_timeline_frames(
+        &self,
+        mid: i64,
+        entry_participant_id: &str,
+        frames: Vec<TimelineFrame>,
+    ) -> Result<()> {
+        db_sqlite::insert_timeline_frames(self, mid, entry_participant_id, frames).await
+    }
+
+    async fn compute_player_summary(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<(i32, i32, i32, f32, f32, Streaks, Value)> {
+        db_sqlite::compute_player_summary(self, puuid, queue).await
+    }
+
+    async fn get_daily_activity(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+    ) -> Result<Vec<DailyActivityEntry>> {
+        db_sqlite::get_daily_activity(self, puuid, queue).await
+    }
+
+    async fn save_rank_snapshot(&self, puuid: &str, snapshot: &RankSnapshot) -> Result<()> {
+        db_sqlite::save_rank_snapshot(self, puuid, snapshot).await
+    }
+
+    async fn compute_rank_progress_and_cache(
+        &self,
+        puuid: &str,
+        current_tier: &str,
+        current_division: &str,
+        current_lp: i32,
+    ) -> Result<Vec<RankStep>> {
+        db_sqlite::compute_rank_progress_and_cache(
+            self,
+            puuid,
+            current_tier,
+            current_division,
+            current_lp,
+        )
+        .await
+    }
+
+    async fn get_role_stats(&self, puuid: &str, queue: QueueGroup) -> Result<Vec<RoleStats>> {
+        db_sqlite::get_role_stats(self, puuid, queue).await
+    }
+
+    async fn get_matchup_stats(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<MatchupStats>> {
+        db_sqlite::get_matchup_stats(self, puuid, queue, min_games).await
+    }
+
+    async fn get_teammates(
+        &self,
+        puuid: &str,
+        queue: QueueGroup,
+        min_games: i32,
+    ) -> Result<Vec<DbTeammate>> {
+        db_sqlite::get_teammates(self, puuid, queue, min_games).await
+    }
+
+    async fn summarize_matches(
+        &self,
+        match_ids: &[String],
+        puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<Vec<MatchSummary>> {
+        db_sqlite::summarize_matches(self, match_ids, puuid, ddragon_version).await
+    }
+
+    async fn get_match_details(
+        &self,
+        match_id: &str,
+        user_puuid: &str,
+        ddragon_version: &str,
+    ) -> Result<MatchDetails> {
+        db_sqlite::get_match_details_from_db(self, match_id, user_puuid, ddragon_version).await
+    }
+
+    async fn get_backfill_checkpoint(&self, puuid: &str) -> Result<Option<BackfillCheckpoint>> {
+        db_sqlite::get_backfill_checkpoint(self, puuid).await
+    }
+
+    async fn save_backfill_checkpoint(&self, checkpoint: &BackfillCheckpoint) -> Result<()> {
+        db_sqlite::save_backfill_checkpoint(self, checkpoint).await
+    }
+}
@@ -1,150 +1,146 @@
-use anyhow::{anyhow, Result};
-use reqwest::Client;
+use anyhow::Result;
 use serde_json::json;
-use std::collections::HashSet;
 
 use crate::{
-    db_proxy as db,
-    db_proxy::{PgPool, PgTx},
+    region::Platform,
     models::{
-        AccountDto, MatchDto, PlayerOverview, PlayerProfile, PlayerStats, SummonerDto, TopChamp,
-        RankStep,
+        AccountDto, DbMatchRow, MatchDto, TimelineFrame, PlayerOverview, PlayerProfile, PlayerStats, SummonerDto, TopChamp,
+        RankSnapshot, RankStep, Teammate,
     },
-    riot,
+    error::DianaError,
+    ingest,
+    queues::QueueGroup,
+    riot::{self, MatchIdFilter, MatchIdPager, RiotClient},
+    store::{NewMatchDetails, NewSummoner, Store},
 };
 
-pub async fn sync_player_and_get_overview(
-    pool: &PgPool,
-    query_region: &str,
+/// Riot account, summoner and solo queue rank of a player, as last fetched from Riot.
+pub struct SyncedSummoner {
+    pub account: AccountDto,
+    pub summoner: SummonerDto,
+    pub tier: Option<String>,
+    pub division: Option<String>,
+    pub lp: Option<i32>,
+}
+
+/// Looks the player up on Riot and stores their summoner row, plus a snapshot of their
+/// solo queue rank for the LP history.
+///
+/// This is the only part of a sync the overview waits for, matches are fetched by the
+/// background [`crate::sync_manager::SyncManager`].
+pub async fn refresh_summoner(
+    client: &RiotClient,
+    store: &dyn Store,
+    platform: Platform,
     name: &str,
     tag: &str,
-    _api_key: &str,
-) -> Result<PlayerOverview> {
-    eprintln!("[SYNC] Starting sync for player: {}#{} in region {}", name, tag, query_region);
-    let (platform, regional) =
-        riot::map_region(query_region).ok_or_else(|| anyhow!("Unsupported region: {}", query_region))?;
-
-    let client = Client::builder().user_agent("Diana/0.1.0").build()?;
+) -> Result<SyncedSummoner> {
+    eprintln!("[SYNC] Refreshing summoner {}#{} in region {}", name, tag, platform);
+    let account_route = platform.account_route().as_str();
 
     let acct: AccountDto =
-        riot::get_account_by_riot_id(&client, regional, name, tag).await?;
+        riot::get_account_by_riot_id(client, account_route, name, tag).await?;
     let sum: SummonerDto =
-        riot::get_summoner_by_puuid(&client, platform, &acct.puuid).await?;
+        riot::get_summoner_by_puuid(client, platform.as_str(), &acct.puuid).await?;
     let (tier, division, lp) =
-        riot::get_rank_solo(&client, platform, &acct.puuid).await?;
-
-    db::upsert_summoner(
-        pool,
-        &acct.puuid,
-        if sum.name.is_empty() { &acct.gameName } else { &sum.name },
-        &acct.tagLine,
-        &query_region.to_uppercase(),
-        tier.as_deref(),
-        division.as_deref(),
-        lp,
-    )
-    .await?;
+        riot::get_rank_solo(client, platform.as_str(), &acct.puuid).await?;
 
-    // Always ensure we have the 10 most recent matches for this user
-    eprintln!("[SYNC] Fetching 10 most recent match IDs from Riot API...");
-    let fresh_ids = riot::get_match_ids(&client, regional, &acct.puuid, 0, 10).await?;
-    eprintln!("[SYNC] Retrieved {} fresh match IDs from Riot API", fresh_ids.len());
-    
-    if !fresh_ids.is_empty() {
-        // Check which of these 10 matches we already have in the database
-        let mut existing_ids = HashSet::new();
-        for match_id in &fresh_ids {
-            if db::match_exists(pool, match_id).await? {
-                existing_ids.insert(match_id.clone());
-            }
-        }
-        
-        eprintln!("[SYNC] Found {} existing matches out of {} fresh matches", existing_ids.len(), fresh_ids.len());
-        
-        // Determine which matches we need to fetch
-        let mut missing_ids = Vec::new();
-        for match_id in &fresh_ids {
-            if !existing_ids.contains(match_id) {
-                missing_ids.push(match_id.clone());
-            }
-        }
-        
+    store
+        .upsert_summoner(NewSummoner {
+            puuid: &acct.puuid,
+            game_name: if sum.name.is_empty() { &acct.gameName } else { &sum.name },
+            tag_line: &acct.tagLine,
+            region: platform.short_name(),
+            match_region_prefix: &platform.match_id_prefix(),
+            region_group: platform.match_route().as_str(),
+            tier: tier.as_deref(),
+            rank: division.as_deref(),
+            lp,
+        })
+        .await?;
+
+    if let (Some(tier), Some(division), Some(lp)) = (&tier, &division, lp) {
+        let snapshot = RankSnapshot {
+            tier: tier.clone(),
+            division: division.clone(),
+            lp,
+            taken_at: chrono::Utc::now().timestamp_millis(),
+        };
+        store.save_rank_snapshot(&acct.puuid, &snapshot).await?;
+    }
+
+    Ok(SyncedSummoner { account: acct, summoner: sum, tier, division, lp })
+}
+
+/// IDs of the player's matches that are not stored yet, newest first.
+///
+/// Pages back from the newest match until a page reaches stored matches, so a match
+/// that failed to ingest while newer ones went through is picked up by the next sync as
+/// long as it is on that page. With nothing stored yet this falls back to the 10 most
+/// recent matches. When the player has not played since, it costs a single match ID
+/// request and one database lookup.
+pub async fn new_match_ids(
+    client: &RiotClient,
+    store: &dyn Store,
+    regional: &str,
+    puuid: &str,
+) -> Result<Vec<String>> {
+    let Some(latest) = store.latest_match_for_puuid(puuid).await? else {
+        eprintln!("[SYNC] No stored matches for {}, fetching the 10 most recent", puuid);
+        let fresh_ids =
+            riot::get_match_ids(client, regional, puuid, 0, 10, &MatchIdFilter::default()).await?;
+        let missing_ids = ingest::filter_missing(store, fresh_ids).await?;
         eprintln!("[SYNC] Need to fetch {} missing matches: {:?}", missing_ids.len(), missing_ids);
-        
-        // For new users (no existing matches), we need to balance speed vs completeness
-        let is_new_user = existing_ids.is_empty();
-        let skip_timeline = is_new_user;
-        
-        if is_new_user && missing_ids.len() > 3 {
-            eprintln!("[SYNC] New user with {} matches - fetching first 3 immediately, rest will be fetched in background", missing_ids.len());
-            
-            // Fetch only the first 3 matches immediately to avoid timeout
-            let immediate_matches = missing_ids.iter().take(3).cloned().collect::<Vec<_>>();
-            for (idx, match_id) in immediate_matches.iter().enumerate() {
-                eprintln!("[SYNC] Fetching immediate match {}/{}: {}", idx + 1, immediate_matches.len(), match_id);
-                insert_match_with_options(&client, pool, regional, &acct.puuid, match_id, skip_timeline).await?;
-            }
-            
-            eprintln!("[SYNC] Will fetch remaining {} matches in background later", missing_ids.len() - 3);
-            // Note: The remaining matches will be fetched on subsequent logins
-        } else {
-            // Existing user or small number of missing matches - fetch all
-            if skip_timeline {
-                eprintln!("[SYNC] New user with {} matches - will skip timelines for faster sync", missing_ids.len());
-            }
-            
-            for (idx, match_id) in missing_ids.iter().enumerate() {
-                eprintln!("[SYNC] Fetching match {}/{}: {}", idx + 1, missing_ids.len(), match_id);
-                insert_match_with_options(&client, pool, regional, &acct.puuid, match_id, skip_timeline).await?;
-            }
+        return Ok(missing_ids);
+    };
+
+    let mut pager = MatchIdPager::new(client, regional, puuid, MatchIdFilter::default());
+    let mut missing_ids = Vec::new();
+    while let Some(page) = pager.next_page().await? {
+        let page_len = page.len();
+        let missing = ingest::filter_missing(store, page).await?;
+        let reached_stored = missing.len() < page_len;
+        missing_ids.extend(missing);
+        if reached_stored {
+            break;
         }
-        
-        eprintln!("[SYNC] Sync complete - fetched new matches as needed");
-    } else {
-        eprintln!("[SYNC] ⚠️  No matches found for user from Riot API");
     }
+    eprintln!(
+        "[SYNC] {} matches missing, newest stored is {} ({})",
+        missing_ids.len(),
+        latest.matchId,
+        latest.gameCreation
+    );
+    Ok(missing_ids)
+}
 
-    let ddragon_version = riot::get_latest_ddragon_version(&client).await?;
-    eprintln!("[SYNC] DDragon version: {}", ddragon_version);
-    
-    let recent_matches = db::get_recent_matches(pool, &acct.puuid, 10).await?;
+/// Builds the overview from what is already in the database.
+pub async fn get_cached_overview(
+    store: &dyn Store,
+    platform: Platform,
+    player: &SyncedSummoner,
+    queue: QueueGroup,
+    ddragon_version: &str,
+) -> Result<PlayerOverview> {
+    let SyncedSummoner { account: acct, summoner: sum, tier, division, lp } = player;
+    let lp = *lp;
+
+    let recent_matches = store.get_recent_matches(&acct.puuid, 10).await?;
     eprintln!("[SYNC] Found {} recent matches in database for puuid: {}", recent_matches.len(), &acct.puuid);
     
     if recent_matches.is_empty() {
-        eprintln!("[SYNC] ⚠️  No matches found in database! This is unexpected after sync.");
+        eprintln!("[SYNC] No matches stored yet, the background sync will fill them in");
     } else {
         eprintln!("[SYNC] Recent match IDs: {:?}", recent_matches.iter().map(|m| &m.matchId).collect::<Vec<_>>());
     }
 
-    let mut matches = Vec::with_capacity(recent_matches.len());
-    let mut successful_summaries = 0;
-    let mut failed_summaries = 0;
-    
-    for (idx, row) in recent_matches.iter().enumerate() {
-        eprintln!("[SYNC] Processing match {}/{}: {}", idx + 1, recent_matches.len(), row.matchId);
-        
-        match crate::db_proxy::summarize_match_from_db(
-            pool,
-            &row.matchId,
-            &acct.puuid,
-            &ddragon_version,
-        ).await {
-            Ok(ms) => {
-                eprintln!("[SYNC] ✅ Successfully summarized match {}: {} vs {} ({})", 
-                         row.matchId, ms.champion_name, 
-                         if ms.win { "WIN" } else { "LOSS" }, ms.kda);
-                matches.push(ms);
-                successful_summaries += 1;
-            }
-            Err(e) => {
-                eprintln!("[SYNC] ❌ Failed to summarize match {}: {}", row.matchId, e);
-                failed_summaries += 1;
-            }
-        }
-    }
-    
-    eprintln!("[SYNC] Match summarization complete: {} successful, {} failed, {} total matches to return", 
-              successful_summaries, failed_summaries, matches.len());
+    let match_ids: Vec<String> = recent_matches.iter().map(|m| m.matchId.clone()).collect();
+    let matches = store.summarize_matches(&match_ids, &acct.puuid, ddragon_version).await?;
+    eprintln!(
+        "[SYNC] Match summarization complete: {} of {} matches summarized",
+        matches.len(),
+        match_ids.len()
+    );
 
     let profile_icon_url = format!(
         "https://ddragon.leagueoflegends.com/cdn/{}/img/profileicon/{}.png",
@@ -155,7 +151,7 @@ pub async fn sync_player_and_get_overview(
         puuid: acct.puuid.clone(),
         name: if sum.name.is_empty() { acct.gameName.clone() } else { sum.name.clone() },
         tagline: acct.tagLine.clone(),
-        region: query_region.to_uppercase(),
+        region: platform.short_name().to_string(),
         summoner_level: sum.summonerLevel as u32,
         profile_icon_url,
         tier: tier.clone(),
@@ -163,22 +159,25 @@ pub async fn sync_player_and_get_overview(
         lp,
     };
 
-    let (games, _wins, _losses, avg_kda, winrate, streak, top_champs_json) =
-        crate::db_proxy::compute_player_summary(pool, &acct.puuid).await?;
+    let (games, _wins, _losses, avg_kda, winrate, streaks, top_champs_json) =
+        store.compute_player_summary(&acct.puuid, queue).await?;
     let top_champs: Vec<TopChamp> =
         serde_json::from_value(top_champs_json).unwrap_or_else(|_| vec![]);
 
     let stats = PlayerStats {
         winrate,
         games,
-        streak,
+        streak: streaks.current,
+        longest_win_streak: streaks.longest_win,
+        longest_loss_streak: streaks.longest_loss,
         kda: avg_kda,
     };
 
-    let ranked_progress: Vec<RankStep> = if let (Some(ref t), Some(ref d), Some(lp_val)) =
-        (profile.tier.as_ref(), profile.division.as_ref(), profile.lp)
+    let ranked_progress: Vec<RankStep> = if let (Some(t), Some(d), Some(lp_val)) =
+        (profile.tier.as_deref(), profile.division.as_deref(), profile.lp)
     {
-        crate::db_proxy::compute_rank_progress_and_cache(pool, &acct.puuid, t, d, lp_val)
+        store
+            .compute_rank_progress_and_cache(&acct.puuid, t, d, lp_val)
             .await
             .unwrap_or_default()
     } else {
@@ -191,91 +190,195 @@ pub async fn sync_player_and_get_overview(
         stats,
         top_champs,
         ranked_progress,
+        queue,
+        syncing: false,
     })
 }
 
-async fn insert_match_with_options(
-    client: &Client,
-    pool: &PgPool,
+/// Frequent teammates of a stored player, named by the Riot IDs in the stored matches.
+/// Teammates without one are looked up on the player's account route, a failed lookup
+/// only costs their names.
+pub async fn get_teammates(
+    client: &RiotClient,
+    store: &dyn Store,
+    puuid: &str,
+    queue: QueueGroup,
+    min_games: i32,
+) -> Result<Vec<Teammate>> {
+    let summoner = store
+        .get_summoner(puuid)
+        .await?
+        .ok_or_else(|| DianaError::PlayerNotFound(puuid.to_string()))?;
+    let platform: Platform = summoner.region.parse()?;
+
+    let rows = store.get_teammates(puuid, queue, min_games).await?;
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Riot IDs come with the stored matches, only older ones without them need a lookup.
+    let unnamed: Vec<String> =
+        rows.iter().filter(|r| r.riot_id.is_none()).map(|r| r.puuid.clone()).collect();
+    let names = if unnamed.is_empty() {
+        Default::default()
+    } else {
+        let account_route = platform.account_route().as_str();
+        riot::get_summoner_names_by_puuids(client, account_route, &unnamed)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("[SYNC] Could not resolve teammate names: {:#}", e);
+                Default::default()
+            })
+    };
+
+    let winrate =
+        |wins: i32, games: i32| if games > 0 { wins as f32 / games as f32 * 100.0 } else { 0.0 };
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let name = row.riot_id.or_else(|| names.get(&row.puuid).cloned()).unwrap_or_else(|| {
+                let short: String = row.puuid.chars().take(8).collect();
+                format!("Player {}", short)
+            });
+            let games_without = row.total_games - row.games;
+            Teammate {
+                name,
+                games_together: row.games,
+                wins_together: row.wins,
+                winrate_together: winrate(row.wins, row.games),
+                games_without,
+                winrate_without: winrate(row.total_wins - row.wins, games_without),
+                puuid: row.puuid,
+            }
+        })
+        .collect())
+}
+
+/// Makes sure a single match is in the database, fetching it with its timeline if it is not.
+pub async fn ensure_match_stored(
+    client: &RiotClient,
+    store: &dyn Store,
     regional: &str,
     entry_puuid: &str,
     match_id: &str,
-    skip_timeline: bool,
 ) -> Result<()> {
-    if db::match_exists(pool, match_id).await? {
+    insert_match_with_options(client, store, regional, entry_puuid, match_id, false).await?;
+    Ok(())
+}
+
+/// Fetches and stores one match. Returns its `gameCreation` when it was inserted,
+/// `None` when it was already stored.
+pub(crate) async fn insert_match_with_options(
+    client: &RiotClient,
+    store: &dyn Store,
+    regional: &str,
+    entry_puuid: &str,
+    match_id: &str,
+    skip_timeline: bool,
+) -> Result<Option<i64>> {
+    if store.match_exists(match_id).await? {
         println!("[DB] Match {} already exists, skipping insert.", match_id);
-        return Ok(());
+        return Ok(None);
     }
 
-    let m: MatchDto = crate::riot::get_match_by_id(client, regional, match_id).await?;
-    
-    let timeline = if skip_timeline {
-        println!("[SYNC] Skipping timeline fetch for match {} to speed up initial sync", match_id);
-        serde_json::Value::Null
-    } else {
-        println!("[SYNC] Fetching timeline for match {}", match_id);
-        crate::riot::get_timeline_by_id(client, regional, match_id).await?
+    let fetch_timeline = async {
+        if skip_timeline {
+            println!("[SYNC] Skipping timeline fetch for match {}", match_id);
+            Ok(serde_json::Value::Null)
+        } else {
+            println!("[SYNC] Fetching timeline for match {}", match_id);
+            crate::riot::get_timeline_by_id(client, regional, match_id).await
+        }
     };
+    let (m, timeline): (MatchDto, serde_json::Value) = tokio::try_join!(
+        crate::riot::get_match_by_id(client, regional, match_id),
+        fetch_timeline,
+    )?;
 
     let participants_json = serde_json::to_value(&m.info.participants)?;
     let teams_json = json!({ "queueId": m.info.queueId });
 
-    let mut tx: PgTx<'_> = pool.begin().await?;
-
-    let mid = db::insert_match_details_tx(
-        &mut tx,
-        match_id,
-        entry_puuid,
-        None,
-        Some(m.info.gameCreation),
-        None,
-        None,
-        Some(m.info.gameDuration as i32),
-        None,
-        None,
-        Some(m.info.queueId),
-        None,
-        &participants_json,
-        &teams_json,
-    )
-    .await?;
+    let mut tx = store.begin().await?;
+
+    let mid = tx
+        .insert_match_details(NewMatchDetails {
+            match_id,
+            entry_puuid,
+            game_version: None,
+            game_creation: Some(m.info.gameCreation),
+            game_start: None,
+            game_end: None,
+            game_duration: Some(m.info.gameDuration as i32),
+            game_mode: None,
+            game_type: None,
+            queue_type: Some(m.info.queueId),
+            map_name: m.info.mapId,
+            participants: &participants_json,
+            teams: &teams_json,
+        })
+        .await?;
 
     // Only process timeline if not skipped
     if !skip_timeline {
-        if let Some(frames) = timeline
-            .get("info")
-            .and_then(|i| i.get("frames"))
-            .and_then(|f| f.as_array())
-        {
-            for (idx, frame) in frames.iter().enumerate() {
-                if db::timeline_frame_exists_tx(&mut tx, mid, idx as i32).await? {
-                    println!(
-                        "[DB] Timeline frame {} for match {} exists, skipping.",
-                        idx, match_id
-                    );
-                    continue;
-                }
-
-                let ts = frame.get("timestamp").and_then(|t| t.as_i64());
-                let participant_frames =
-                    frame.get("participantFrames").cloned().unwrap_or_else(|| json!({}));
-                let events = frame.get("events").cloned().unwrap_or_else(|| json!([]));
-
-                db::insert_timeline_frame_tx(
-                    &mut tx,
-                    mid,
-                    entry_puuid,
-                    Some(idx as i32),
-                    ts,
-                    &participant_frames,
-                    &events,
-                )
-                .await?;
+        tx.insert_timeline_frames(mid, entry_puuid, timeline_frames(&timeline)).await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(m.info.gameCreation))
+}
+
+/// Fetches the timeline of a match that was stored with `skip_timeline` and stores its frames.
+///
+/// A timeline Riot does not have, or returns without frames, is marked unavailable so
+/// later runs move on to older matches. Other failures are left to be retried.
+pub(crate) async fn store_missing_timeline(
+    client: &RiotClient,
+    store: &dyn Store,
+    row: &DbMatchRow,
+) -> Result<()> {
+    let regional = Platform::from_match_id(&row.matchId)?.match_route();
+    println!("[SYNC] Fetching missing timeline for match {}", row.matchId);
+    let timeline = match riot::get_timeline_by_id(client, regional.as_str(), &row.matchId).await {
+        Ok(timeline) => timeline,
+        Err(e) => {
+            if matches!(e.downcast_ref::<DianaError>(), Some(DianaError::MatchNotFound(_))) {
+                store.mark_timeline_unavailable(row.mid, "not found").await?;
             }
+            return Err(e);
         }
+    };
+
+    let frames = timeline_frames(&timeline);
+    if frames.is_empty() {
+        store.mark_timeline_unavailable(row.mid, "no frames").await?;
+        anyhow::bail!("Timeline of match {} has no frames", row.matchId);
     }
 
-    tx.commit().await?;
+    store.insert_timeline_frames(row.mid, &row.entryPlayerPuuid, frames).await?;
     Ok(())
 }
 
+/// Frames of a timeline response in the shape `match_timeline` stores them.
+fn timeline_frames(timeline: &serde_json::Value) -> Vec<TimelineFrame> {
+    let Some(frames) = timeline
+        .get("info")
+        .and_then(|i| i.get("frames"))
+        .and_then(|f| f.as_array())
+    else {
+        return Vec::new();
+    };
+
+    frames
+        .iter()
+        .enumerate()
+        .map(|(idx, frame)| TimelineFrame {
+            frame_index: idx as i32,
+            timestamp: frame.get("timestamp").and_then(|t| t.as_i64()),
+            participant_frames: frame
+                .get("participantFrames")
+                .cloned()
+                .unwrap_or_else(|| json!({})),
+            events: frame.get("events").cloned().unwrap_or_else(|| json!([])),
+        })
+        .collect()
+}
@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+use crate::{
+    app_state::AppState,
+    ingest::{self, IngestOptions, MatchIngestResult},
+    region::Platform,
+    sync,
+};
+
+/// Event the frontend listens on for sync updates.
+pub const SYNC_PROGRESS_EVENT: &str = "sync://progress";
+
+/// A player is not synced again within this long of their last sync finishing,
+/// so refetching the overview after a sync does not queue another one.
+const MIN_RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timelines filled in per sync for matches stored without one.
+const TIMELINES_PER_SYNC: i64 = 20;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStage {
+    Queued,
+    MatchIds,
+    Matches,
+    Timelines,
+    Done,
+    Failed,
+}
+
+/// Payload of [`SYNC_PROGRESS_EVENT`].
+///
+/// Updates while a job runs carry the counters and the result that prompted them; the
+/// full `results` and `errors` only come with the final `done` or `failed` update.
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncProgress {
+    pub puuid: String,
+    pub stage: SyncStage,
+    pub done: usize,
+    pub total: usize,
+    /// Matches and timelines that could not be stored so far.
+    pub failed: usize,
+    /// Result of the match (or timeline) that finished last, `None` until one has.
+    pub latest: Option<MatchIngestResult>,
+    /// Outcome of every match (and then timeline) handled, in completion order.
+    pub results: Vec<MatchIngestResult>,
+    /// Matches that could not be stored, plus the error that ended the job if it failed.
+    pub errors: Vec<String>,
+}
+
+impl SyncProgress {
+    fn new(puuid: String, stage: SyncStage) -> Self {
+        Self {
+            puuid,
+            stage,
+            done: 0,
+            total: 0,
+            failed: 0,
+            latest: None,
+            results: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Records a finished match or timeline, `error` is the message kept for the final update.
+    fn record(&mut self, result: &MatchIngestResult, error: Option<String>) {
+        if let Some(error) = error {
+            self.failed += 1;
+            self.errors.push(error);
+        }
+        self.results.push(result.clone());
+        self.latest = Some(result.clone());
+        self.done += 1;
+    }
+
+    /// What is sent for this state: everything once the job ended, otherwise the same
+    /// without the full lists.
+    fn payload(&self) -> SyncProgress {
+        if matches!(self.stage, SyncStage::Done | SyncStage::Failed) {
+            return self.clone();
+        }
+        SyncProgress {
+            puuid: self.puuid.clone(),
+            stage: self.stage,
+            done: self.done,
+            total: self.total,
+            failed: self.failed,
+            latest: self.latest.clone(),
+            results: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncJob {
+    pub puuid: String,
+    pub platform: Platform,
+}
+
+/// Runs player syncs in the background, one job at a time, and reports progress
+/// to the frontend. Held in Tauri managed state.
+pub struct SyncManager {
+    app: AppHandle,
+    jobs: mpsc::UnboundedSender<SyncJob>,
+    queued: Arc<Mutex<HashSet<String>>>,
+    last_finished: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl SyncManager {
+    /// Starts the worker task. Must be called from within the Tauri runtime.
+    pub fn start(app: AppHandle) -> Self {
+        let (jobs, mut rx) = mpsc::unbounded_channel::<SyncJob>();
+        let queued = Arc::new(Mutex::new(HashSet::new()));
+        let last_finished = Arc::new(Mutex::new(HashMap::new()));
+
+        let worker_queued = queued.clone();
+        let worker_finished = last_finished.clone();
+        let worker_app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let app = worker_app;
+            while let Some(job) = rx.recv().await {
+                let mut progress = SyncProgress::new(job.puuid.clone(), SyncStage::MatchIds);
+                if let Err(e) = run_job(&app, &job, &mut progress).await {
+                    eprintln!("[SYNC] Background sync for {} failed: {:#}", job.puuid, e);
+                    progress.stage = SyncStage::Failed;
+                    progress.errors.push(format!("{:#}", e));
+                    emit(&app, &progress);
+                }
+                worker_queued.lock().unwrap().remove(&job.puuid);
+                worker_finished.lock().unwrap().insert(job.puuid, Instant::now());
+            }
+        });
+
+        Self { app, jobs, queued, last_finished }
+    }
+
+    /// Queues a sync for the player. Returns false when one is already queued or
+    /// running, or when the player was synced moments ago.
+    pub fn enqueue(&self, job: SyncJob) -> bool {
+        let recently_synced = self
+            .last_finished
+            .lock()
+            .unwrap()
+            .get(&job.puuid)
+            .is_some_and(|at| at.elapsed() < MIN_RESYNC_INTERVAL);
+        if recently_synced || !self.queued.lock().unwrap().insert(job.puuid.clone()) {
+            return false;
+        }
+
+        let puuid = job.puuid.clone();
+        // Emitted before the send so it cannot arrive after the worker's first update.
+        emit(&self.app, &SyncProgress::new(puuid.clone(), SyncStage::Queued));
+        if self.jobs.send(job).is_err() {
+            self.queued.lock().unwrap().remove(&puuid);
+            return false;
+        }
+        eprintln!("[SYNC] Queued background sync for {}", puuid);
+        true
+    }
+
+    pub fn is_syncing(&self, puuid: &str) -> bool {
+        self.queued.lock().unwrap().contains(puuid)
+    }
+}
+
+fn emit(app: &AppHandle, progress: &SyncProgress) {
+    if let Err(e) = app.emit(SYNC_PROGRESS_EVENT, progress.payload()) {
+        eprintln!("[SYNC] Failed to emit progress: {}", e);
+    }
+}
+
+async fn run_job(app: &AppHandle, job: &SyncJob, progress: &mut SyncProgress) -> Result<()> {
+    let state = app.state::<AppState>();
+    let (client, store) = (&state.client, state.store.as_ref());
+    let regional = job.platform.match_route().as_str();
+
+    emit(app, progress);
+    let missing = sync::new_match_ids(client, store, regional, &job.puuid).await?;
+
+    progress.stage = SyncStage::Matches;
+    progress.total = missing.len();
+    emit(app, progress);
+
+    ingest::ingest_matches(
+        client,
+        store,
+        regional,
+        &job.puuid,
+        &missing,
+        IngestOptions::from_env(false),
+        |result| {
+            let error = match &result.outcome {
+                ingest::MatchOutcome::Failed { error, .. } => Some(format!("{}: {}", result.match_id, error)),
+                _ => None,
+            };
+            progress.record(result, error);
+            emit(app, progress);
+        },
+    )
+    .await;
+
+    // Fill in timelines of matches that were stored without one, e.g. by a history backfill.
+    let missing_timelines =
+        store.get_matches_missing_timeline(&job.puuid, TIMELINES_PER_SYNC).await?;
+    if !missing_timelines.is_empty() {
+        progress.stage = SyncStage::Timelines;
+        progress.done = 0;
+        progress.total = missing_timelines.len();
+        emit(app, progress);
+
+        ingest::ingest_timelines(
+            client,
+            store,
+            &missing_timelines,
+            ingest::concurrency_from_env(),
+            |result| {
+                let error = match &result.outcome {
+                    ingest::MatchOutcome::Failed { error, .. } => {
+                        Some(format!("{} timeline: {}", result.match_id, error))
+                    }
+                    _ => None,
+                };
+                progress.record(result, error);
+                emit(app, progress);
+            },
+        )
+        .await;
+    }
+
+    progress.stage = SyncStage::Done;
+    emit(app, progress);
+    eprintln!(
+        "[SYNC] Background sync for {} done: {}/{} matches, {} errors",
+        job.puuid,
+        progress.done,
+        progress.total,
+        progress.errors.len()
+    );
+    Ok(())
+}
@@ -14,6 +14,8 @@ export type ProfileProps = {
     winrate?: number;
     games?: number;
     streak?: number;
+    longest_win_streak?: number;
+    longest_loss_streak?: number;
     kda?: number;
   };
 };
@@ -89,6 +91,12 @@ const ProfileCard: React.FC<ProfileProps> = ({
           <span className="label">Avg KDA</span>
           <span className="value">{(stats?.kda ?? 0).toFixed(2)}</span>
         </div>
+        <div className="quick-pill">
+          <span className="label">Longest streaks</span>
+          <span className="value">
+            W{stats?.longest_win_streak ?? 0} / L{stats?.longest_loss_streak ?? 0}
+          </span>
+        </div>
       </div>
     </aside>
   );
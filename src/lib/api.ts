@@ -1,17 +1,25 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   PlayerOverview,
   MatchDetails,
   DailyActivityEntry,
+  MatchIngestResult,
+  MatchupStats,
+  QueueGroup,
+  RoleStats,
+  SyncProgress,
+  Teammate,
 } from "../types/riot";
 
 export async function fetchOverview(
   name: string,
   region: string,
   tag: string,
+  queue: QueueGroup = "ranked_solo",
 ): Promise<PlayerOverview> {
   return invoke<PlayerOverview>("get_player_overview", {
-    query: { name, region, tag },
+    query: { name, region, tag, queue },
   });
 }
 
@@ -26,8 +34,46 @@ export async function fetchMatchDetails(
 
 export async function fetchDailyActivity(
   userPuuid: string,
+  queue: QueueGroup = "ranked_solo",
 ): Promise<DailyActivityEntry[]> {
   return invoke<DailyActivityEntry[]>("get_daily_activity", {
-    query: { user_puuid: userPuuid },
+    query: { user_puuid: userPuuid, queue },
   });
 }
+
+export async function fetchRoleStats(
+  puuid: string,
+  queue: QueueGroup = "ranked_solo",
+): Promise<RoleStats[]> {
+  return invoke<RoleStats[]>("get_role_stats", { puuid, queue });
+}
+
+export async function fetchMatchupStats(
+  puuid: string,
+  queue: QueueGroup = "ranked_solo",
+  minGames?: number,
+): Promise<MatchupStats[]> {
+  return invoke<MatchupStats[]>("get_matchup_stats", { puuid, queue, minGames });
+}
+
+export async function fetchTeammates(
+  puuid: string,
+  queue: QueueGroup = "ranked_solo",
+  minGames?: number,
+): Promise<Teammate[]> {
+  return invoke<Teammate[]>("get_teammates", { puuid, queue, minGames });
+}
+
+export async function backfillTimelines(
+  puuid: string,
+): Promise<MatchIngestResult[]> {
+  return invoke<MatchIngestResult[]>("backfill_timelines", { puuid });
+}
+
+export function onSyncProgress(
+  handler: (progress: SyncProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<SyncProgress>("sync://progress", (event) =>
+    handler(event.payload),
+  );
+}
@@ -1,6 +1,12 @@
-import React, { useEffect, useState } from "react";
-import { fetchOverview, fetchDailyActivity } from "../lib/api";
-import type { PlayerOverview, DailyActivityEntry } from "../types/riot";
+import React, { useCallback, useEffect, useState } from "react";
+import { fetchOverview, fetchDailyActivity, onSyncProgress } from "../lib/api";
+import type {
+  PlayerOverview,
+  DailyActivityEntry,
+  QueueGroup,
+  SyncProgress,
+} from "../types/riot";
+import { QUEUE_GROUPS } from "../utils/format";
 import { useAuth } from "../contexts/AuthContext";
 import ProfileCard from "../components/ProfileCard";
 import MatchList from "../components/MatchList";
@@ -13,36 +19,57 @@ const Home: React.FC = () => {
   const [activityData, setActivityData] = useState<DailyActivityEntry[]>([]);
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(true);
+  const [syncProgress, setSyncProgress] = useState<SyncProgress | null>(null);
+  const [queue, setQueue] = useState<QueueGroup>("ranked_solo");
   const { user } = useAuth();
 
-  useEffect(() => {
+  const load = useCallback(async () => {
     if (!user) return;
 
-    (async () => {
+    try {
+      const res = await fetchOverview(user.name, user.region, user.tag, queue);
+
+      if (res.matches.length === 0) {
+        console.warn("[Frontend] ⚠️  No matches received from backend!");
+      } else {
+        console.debug("[Frontend] ✅ First match:", res.matches[0]);
+      }
+
+      setData(res);
       try {
-        const res = await fetchOverview(user.name, user.region, user.tag);
-
-        if (res.matches.length === 0) {
-          console.warn("[Frontend] ⚠️  No matches received from backend!");
-        } else {
-          console.debug("[Frontend] ✅ First match:", res.matches[0]);
-        }
-
-        setData(res);
-        try {
-          const activityRes = await fetchDailyActivity(res.profile.puuid);
-          setActivityData(activityRes);
-        } catch (activityError) {
-          console.error("Failed to fetch activity data:", activityError);
-        }
-      } catch (e: any) {
-        console.error("[Frontend] ❌ fetchOverview failed:", e);
-        setError(e?.message ?? String(e));
-      } finally {
-        setLoading(false);
+        const activityRes = await fetchDailyActivity(res.profile.puuid, queue);
+        setActivityData(activityRes);
+      } catch (activityError) {
+        console.error("Failed to fetch activity data:", activityError);
       }
-    })();
-  }, [user]);
+    } catch (e: any) {
+      console.error("[Frontend] ❌ fetchOverview failed:", e);
+      setError(e?.message ?? String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [user, queue]);
+
+  useEffect(() => {
+    load();
+  }, [load]);
+
+  // Matches are synced in the background; reload once the sync for this player finishes.
+  const puuid = data?.profile.puuid;
+  useEffect(() => {
+    if (!puuid) return;
+
+    const unlisten = onSyncProgress((progress) => {
+      if (progress.puuid !== puuid) return;
+      setSyncProgress(progress);
+      if (progress.stage === "done" && progress.done > 0) {
+        load();
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [puuid, load]);
 
   if (loading) return <LoadingSpinner />;
   if (error)
@@ -54,11 +81,27 @@ const Home: React.FC = () => {
   if (!data) return null;
 
   const { profile, matches, stats, top_champs } = data;
+  const syncing =
+    syncProgress !== null
+      ? syncProgress.stage !== "done" && syncProgress.stage !== "failed"
+      : data.syncing;
 
   return (
     <>
       <div className="container grid">
         <div className="left-col">
+          <select
+            value={queue}
+            onChange={(e) => setQueue(e.target.value as QueueGroup)}
+            className="login-select queue-select"
+          >
+            {QUEUE_GROUPS.map((q) => (
+              <option key={q.value} value={q.value}>
+                {q.label}
+              </option>
+            ))}
+          </select>
+
           <ProfileCard
             name={profile.name}
             tagline={profile.tagline}
@@ -77,6 +120,14 @@ const Home: React.FC = () => {
         </div>
 
         <div className="right-col">
+          {syncing && (
+            <div className="sync-status">
+              Syncing matches
+              {syncProgress?.stage === "matches" &&
+                ` (${syncProgress.done}/${syncProgress.total})`}
+              ...
+            </div>
+          )}
           <MatchList matches={matches} />
         </div>
       </div>
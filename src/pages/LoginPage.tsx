@@ -16,6 +16,12 @@ const REGIONS = [
   { code: "OCE", name: "OCE" },
   { code: "TR", name: "TR" },
   { code: "RU", name: "RU" },
+  { code: "ME", name: "ME" },
+  { code: "PH", name: "PH" },
+  { code: "SG", name: "SG" },
+  { code: "TH", name: "TH" },
+  { code: "TW", name: "TW" },
+  { code: "VN", name: "VN" },
 ];
 
 const LoginPage: React.FC = () => {
@@ -68,7 +74,26 @@ const LoginPage: React.FC = () => {
         error?.message ||
         "Failed to find summoner. Please check your details and try again.";
 
-      if (errorMessage.includes("404") || errorMessage.includes("not found")) {
+      const errorCode: string | undefined = error?.code;
+
+      if (errorCode === "player_not_found") {
+        showError(
+          "Summoner not found. Please check your summoner name and tag.",
+        );
+      } else if (errorCode === "proxy_unavailable") {
+        showError("Cannot reach diana-proxy. Please make sure it is running.");
+      } else if (errorCode === "riot_key_expired") {
+        showError("The Riot API key has expired. Please update it.");
+      } else if (errorCode === "rate_limited") {
+        showError(
+          error?.retry_after
+            ? `Too many requests. Please try again in ${error.retry_after}s.`
+            : "Too many requests. Please wait a moment and try again.",
+        );
+      } else if (
+        errorMessage.includes("404") ||
+        errorMessage.includes("not found")
+      ) {
         showError(
           "Summoner not found. Please check your summoner name and tag.",
         );
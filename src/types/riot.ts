@@ -1,14 +1,53 @@
+/** Queues the stats can be narrowed to, see `queues.rs`. */
+export type QueueGroup =
+  | "ranked_solo"
+  | "ranked_flex"
+  | "normal"
+  | "aram"
+  | "arena"
+  | "all";
+
 export type PlayerOverview = {
   profile: PlayerProfile;
   matches: MatchSummary[];
   stats: {
     winrate: number;
     games: number;
+    /** Positive for wins, negative for losses. */
     streak: number;
+    longest_win_streak: number;
+    longest_loss_streak: number;
     kda: number;
   };
   top_champs: TopChamp[];
   ranked_progress: RankStep[];
+  queue: QueueGroup;
+  syncing: boolean;
+};
+
+export type SyncStage =
+  | "queued"
+  | "match_ids"
+  | "matches"
+  | "timelines"
+  | "done"
+  | "failed";
+
+export type MatchIngestResult =
+  | { match_id: string; status: "inserted"; game_creation: number }
+  | { match_id: string; status: "already_stored" }
+  | { match_id: string; status: "failed"; error: string; retryable: boolean };
+
+/** Updates carry the counters and the latest result, `results` and `errors` only come with the final "done" or "failed" one. */
+export type SyncProgress = {
+  puuid: string;
+  stage: SyncStage;
+  done: number;
+  total: number;
+  failed: number;
+  latest: MatchIngestResult | null;
+  results: MatchIngestResult[];
+  errors: string[];
 };
 
 export type PlayerProfile = {
@@ -118,3 +157,59 @@ export type DailyActivityEntry = {
   date: string;
   games: number;
 };
+
+export type Role = "TOP" | "JUNGLE" | "MIDDLE" | "BOTTOM" | "UTILITY";
+
+export type RoleStats = {
+  role: Role;
+  games: number;
+  wins: number;
+  /** 0 to 100 */
+  winrate: number;
+  kda: number;
+  cs_per_min: number;
+  gold_per_min: number;
+  vision_per_min: number;
+  /** 0 to 100, unlike the per-match kill_participation */
+  kill_participation: number;
+  /** Games with an empty teamPosition that got a role inferred */
+  inferred_games: number;
+};
+
+export type MatchupStats = {
+  champion_name: string;
+  opponent_champion_name: string;
+  role: Role;
+  games: number;
+  wins: number;
+  winrate: number;
+  avg_kda_diff: number;
+  avg_gold_diff: number;
+};
+
+export type Teammate = {
+  puuid: string;
+  name: string;
+  games_together: number;
+  wins_together: number;
+  winrate_together: number;
+  games_without: number;
+  winrate_without: number;
+};
+
+export type DianaErrorCode =
+  | "player_not_found"
+  | "match_not_found"
+  | "invalid_region"
+  | "proxy_unavailable"
+  | "rate_limited"
+  | "riot_key_expired"
+  | "riot_api"
+  | "database"
+  | "internal";
+
+export type DianaError = {
+  code: DianaErrorCode;
+  message: string;
+  retry_after?: number | null;
+};
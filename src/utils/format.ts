@@ -1,3 +1,5 @@
+import type { QueueGroup } from "../types/riot";
+
 let cachedItems: Record<string, any> | null = null;
 let cachedVersion: string | null = null;
 
@@ -30,6 +32,15 @@ export function queueName(queueId: number): string {
   }
 }
 
+export const QUEUE_GROUPS: { value: QueueGroup; label: string }[] = [
+  { value: "ranked_solo", label: "Ranked Solo/Duo" },
+  { value: "ranked_flex", label: "Ranked Flex" },
+  { value: "normal", label: "Normal" },
+  { value: "aram", label: "ARAM" },
+  { value: "arena", label: "Arena" },
+  { value: "all", label: "All queues" },
+];
+
 export function formatKDA(k: number, d: number, a: number): string {
   return `${k}/${d}/${a}`;
 }